@@ -0,0 +1,188 @@
+use std::array;
+
+use wgpu::TextureFormat;
+
+/// Storage of the canvas, colors are premultiplied by alpha in both formats.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CanvasFormat {
+    // Encoded in sRGB, dark colors would get few of the 8-bit values in linear light and show banding.
+    Rgba8,
+    // In linear light.
+    Rgba16Float,
+}
+
+impl CanvasFormat {
+    pub const fn texture_format(self) -> TextureFormat {
+        match self {
+            Self::Rgba8 => TextureFormat::Rgba8Unorm,
+            Self::Rgba16Float => TextureFormat::Rgba16Float,
+        }
+    }
+
+    /// Texel format of the storage textures in WGSL.
+    pub const fn storage_format(self) -> &'static str {
+        match self {
+            Self::Rgba8 => "rgba8unorm",
+            Self::Rgba16Float => "rgba16float",
+        }
+    }
+
+    /// Whether the shaders convert the colors from and to sRGB when loading and storing the canvas.
+    pub const fn srgb_encoded(self) -> bool {
+        matches!(self, Self::Rgba8)
+    }
+
+    /// Bytes per pixel.
+    pub const fn pixel_size(self) -> u32 {
+        match self {
+            Self::Rgba8 => 4,
+            Self::Rgba16Float => 8,
+        }
+    }
+
+    /// Converts a straight sRGB color into the bytes of a canvas pixel.
+    pub fn encode(self, [r, g, b, a]: [u8; 4]) -> Vec<u8> {
+        let alpha = f32::from(a) / 255.0;
+        match self {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            Self::Rgba8 => {
+                [r, g, b].iter().map(|channel| (f32::from(*channel) * alpha).round() as u8).chain([a]).collect()
+            }
+            Self::Rgba16Float => [r, g, b]
+                .iter()
+                .map(|channel| srgb_to_linear(f32::from(*channel) / 255.0) * alpha)
+                .chain([alpha])
+                .flat_map(|channel| f16_from_f32(channel).to_le_bytes())
+                .collect(),
+        }
+    }
+
+    /// Converts the bytes of a canvas pixel into a premultiplied color in linear light.
+    pub fn decode(self, pixel: &[u8]) -> [f32; 4] {
+        match self {
+            Self::Rgba8 => {
+                let alpha = f32::from(pixel[3]) / 255.0;
+                if alpha <= 0.0 {
+                    return [0.0; 4];
+                }
+                array::from_fn(
+                    |i| {
+                        if i == 3 { alpha } else { srgb_to_linear(f32::from(pixel[i]) / 255.0 / alpha) * alpha }
+                    },
+                )
+            }
+            Self::Rgba16Float => array::from_fn(|i| f32_from_f16(u16::from_le_bytes([pixel[2 * i], pixel[2 * i + 1]]))),
+        }
+    }
+}
+
+/// Converts a premultiplied color in linear light into a straight sRGB one.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn to_srgb([r, g, b, a]: [f32; 4]) -> [u8; 4] {
+    if a <= 0.0 {
+        return [0; 4];
+    }
+    let encode = |channel: f32| (linear_to_srgb((channel / a).clamp(0.0, 1.0)) * 255.0).round() as u8;
+    [encode(r), encode(g), encode(b), (a.min(1.0) * 255.0).round() as u8]
+}
+
+// https://en.wikipedia.org/wiki/SRGB#Transfer_function_(%22gamma%22)
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 { value * 12.92 } else { 1.055f32.mul_add(value.powf(1.0 / 2.4), -0.055) }
+}
+
+// https://en.wikipedia.org/wiki/Half-precision_floating-point_format
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+const fn f16_from_f32(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = (bits >> 16) as u16 & 0x8000;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent >= 31 {
+        // Keeps NaN a NaN, larger values overflow to infinity.
+        return sign | if value.is_nan() { 0x7e00 } else { 0x7c00 };
+    }
+    if exponent <= 0 {
+        // Subnormal, rounded to nearest.
+        if exponent < -10 {
+            return sign;
+        }
+        let shift = (14 - exponent) as u32;
+        return sign | (((mantissa | 0x80_0000) + (1 << (shift - 1))) >> shift) as u16;
+    }
+    // Rounded to nearest, the carry of the mantissa correctly increments the exponent.
+    sign | (((exponent as u32) << 10) + ((mantissa + 0x1000) >> 13)) as u16
+}
+
+fn f32_from_f16(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f16_round_trip() {
+        for bits in 0..=u16::MAX {
+            let value = f32_from_f16(bits);
+            if value.is_nan() {
+                assert!(f16_from_f32(value) & 0x7c00 == 0x7c00 && f16_from_f32(value) & 0x3ff != 0, "bits {bits:#06x}");
+            } else {
+                assert_eq!(f16_from_f32(value), bits, "bits {bits:#06x}");
+            }
+        }
+    }
+
+    #[test]
+    fn f16_rounding() {
+        assert_eq!(f16_from_f32(1.0), 0x3c00);
+        assert_eq!(f16_from_f32(1.0 + 1.0 / 4096.0), 0x3c00);
+        assert_eq!(f16_from_f32(1.0 + 3.0 / 4096.0), 0x3c01);
+        assert_eq!(f16_from_f32(65520.0), 0x7c00);
+        assert_eq!(f16_from_f32(1e-8), 0);
+        assert_eq!(f16_from_f32(-1e-8), 0x8000);
+    }
+
+    #[test]
+    fn srgb_transfer_function() {
+        assert!(srgb_to_linear(0.0).abs() < 1e-6);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.5) - 0.214_041).abs() < 1e-5);
+        assert!((linear_to_srgb(0.5) - 0.735_357).abs() < 1e-5);
+        // The linear segment meets the curve at the threshold.
+        assert!((srgb_to_linear(0.040_45) - ((0.040_45 + 0.055) / 1.055f32).powf(2.4)).abs() < 1e-6);
+        for value in 0..=255u8 {
+            let value = f32::from(value) / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(value)) - value).abs() < 1e-5, "value {value}");
+        }
+    }
+
+    #[test]
+    fn canvas_pixel_round_trip() {
+        for format in [CanvasFormat::Rgba8, CanvasFormat::Rgba16Float] {
+            for value in 0..=255 {
+                let color = [value, 255 - value, value / 2, 255];
+                assert_eq!(to_srgb(format.decode(&format.encode(color))), color);
+                // Premultiplying by half the alpha loses at most the last bit.
+                let decoded = to_srgb(format.decode(&format.encode([value, 255 - value, value / 2, 128])));
+                for (channel, expected) in decoded.iter().zip([value, 255 - value, value / 2, 128]) {
+                    assert!(channel.abs_diff(expected) <= 1, "value {value}");
+                }
+            }
+            assert_eq!(to_srgb(format.decode(&format.encode([12, 34, 56, 0]))), [0; 4]);
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use std::array;
+
+use egui::{
+    Align,
+    Color32,
+    Context,
+    DragValue,
+    Grid,
+    Layout,
+    Pos2,
+    Sense,
+    Shape,
+    Slider,
+    Stroke,
+    StrokeKind,
+    Ui,
+    Window,
+};
+use ultraviolet::Vec2;
+
+// Length of the side of the curve editor in points.
+const CURVE_EDITOR_SIZE: f32 = 256.0;
+const CURVE_POINT_RADIUS: f32 = 4.0;
+const CURVE_POINT_GRAB_DISTANCE: f32 = 8.0;
+// Each pass of the blurs samples twice the radius per pixel, larger radii would stall the GPU on big canvases.
+const MAX_RADIUS: u32 = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Adjustment {
+    BrightnessContrast,
+    HueSaturation,
+    Levels,
+    Curves,
+    Invert,
+    Grayscale,
+    Posterize,
+    Threshold,
+}
+
+impl Adjustment {
+    pub const ALL: [Self; 8] = [
+        Self::BrightnessContrast,
+        Self::HueSaturation,
+        Self::Levels,
+        Self::Curves,
+        Self::Invert,
+        Self::Grayscale,
+        Self::Posterize,
+        Self::Threshold,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::BrightnessContrast => "Brightness/Contrast",
+            Self::HueSaturation => "Hue/Saturation",
+            Self::Levels => "Levels",
+            Self::Curves => "Curves",
+            Self::Invert => "Invert",
+            Self::Grayscale => "Grayscale",
+            Self::Posterize => "Posterize",
+            Self::Threshold => "Threshold",
+        }
+    }
+}
+
+/// Settings of all adjustments, brightness, contrast, saturation and lightness are in percent and levels and
+/// threshold are in the 0-255 range.
+#[derive(Clone)]
+pub struct AdjustmentParameters {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub hue: f32,
+    pub saturation: f32,
+    pub lightness: f32,
+    pub input_black: f32,
+    pub input_white: f32,
+    pub gamma: f32,
+    pub output_black: f32,
+    pub output_white: f32,
+    /// Control points of the curve in the 0-1 range sorted by `x`, the first and the last point stay on the edges.
+    pub curve: Vec<Vec2>,
+    pub posterize_levels: u32,
+    pub threshold: f32,
+}
+
+impl Default for AdjustmentParameters {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 0.0,
+            hue: 0.0,
+            saturation: 0.0,
+            lightness: 0.0,
+            input_black: 0.0,
+            input_white: 255.0,
+            gamma: 1.0,
+            output_black: 0.0,
+            output_white: 255.0,
+            curve: vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0)],
+            posterize_levels: 4,
+            threshold: 128.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Convolution {
+    GaussianBlur,
+    BoxBlur,
+    UnsharpMask,
+    EdgeDetection,
+    Emboss,
+    Custom,
+}
+
+impl Convolution {
+    pub const ALL: [Self; 6] =
+        [Self::GaussianBlur, Self::BoxBlur, Self::UnsharpMask, Self::EdgeDetection, Self::Emboss, Self::Custom];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::GaussianBlur => "Gaussian blur",
+            Self::BoxBlur => "Box blur",
+            Self::UnsharpMask => "Unsharp mask",
+            Self::EdgeDetection => "Edge detection (Sobel)",
+            Self::Emboss => "Emboss",
+            Self::Custom => "Custom kernel",
+        }
+    }
+
+    /// Whether the filter runs as a horizontal and a vertical pass.
+    pub const fn separable(self) -> bool {
+        matches!(self, Self::GaussianBlur | Self::BoxBlur | Self::UnsharpMask)
+    }
+}
+
+/// Settings of all convolution filters, the amount is in percent and the threshold is in the 0-255 range.
+#[derive(Clone)]
+pub struct ConvolutionParameters {
+    pub radius: u32,
+    pub amount: f32,
+    pub threshold: f32,
+    /// Either 3 or 5.
+    pub kernel_size: usize,
+    /// Row-major 5×5 kernel, 3×3 kernels use the top-left corner.
+    pub kernel: [f32; 25],
+    pub normalize: bool,
+    pub bias: f32,
+}
+
+impl ConvolutionParameters {
+    pub const EMBOSS_KERNEL: [f32; 25] = [
+        -2.0, -1.0, 0.0, 0.0, 0.0, //
+        -1.0, 1.0, 1.0, 0.0, 0.0, //
+        0.0, 1.0, 2.0, 0.0, 0.0, //
+        0.0, 0.0, 0.0, 0.0, 0.0, //
+        0.0, 0.0, 0.0, 0.0, 0.0,
+    ];
+
+    /// Returns a kernel that keeps the image unchanged.
+    pub const fn identity_kernel(size: usize) -> [f32; 25] {
+        let mut kernel = [0.0; 25];
+        kernel[size / 2 * 5 + size / 2] = 1.0;
+        kernel
+    }
+
+    /// Returns the number the weighted sum is divided by.
+    pub fn divisor(&self) -> f32 {
+        let sum: f32 = self.kernel.iter().sum();
+        if self.normalize && sum.abs() > f32::EPSILON { sum } else { 1.0 }
+    }
+}
+
+impl Default for ConvolutionParameters {
+    fn default() -> Self {
+        Self {
+            radius: 5,
+            amount: 50.0,
+            threshold: 0.0,
+            kernel_size: 3,
+            kernel: Self::identity_kernel(3),
+            normalize: true,
+            bias: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Adjustment(Adjustment),
+    Convolution(Convolution),
+}
+
+impl Filter {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Adjustment(adjustment) => adjustment.name(),
+            Self::Convolution(convolution) => convolution.name(),
+        }
+    }
+}
+
+/// Samples the monotone cubic interpolation of the curve control points at 256 evenly spaced positions.
+// https://en.wikipedia.org/wiki/Monotone_cubic_interpolation
+pub fn curve_lookup_table(points: &[Vec2]) -> [f32; 256] {
+    let slopes: Vec<f32> = points.windows(2).map(|p| (p[1].y - p[0].y) / (p[1].x - p[0].x).max(f32::EPSILON)).collect();
+    let mut tangents: Vec<f32> = (0..points.len())
+        .map(|i| match (i.checked_sub(1).and_then(|i| slopes.get(i)), slopes.get(i)) {
+            (Some(&previous), Some(&next)) if previous * next > 0.0 => f32::midpoint(previous, next),
+            (Some(_), Some(_)) | (None, None) => 0.0,
+            (Some(&slope), None) | (None, Some(&slope)) => slope,
+        })
+        .collect();
+    // Limit the tangents, so that the curve doesn't overshoot.
+    for (i, &slope) in slopes.iter().enumerate() {
+        if slope.abs() < f32::EPSILON {
+            tangents[i] = 0.0;
+            tangents[i + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (tangents[i] / slope, tangents[i + 1] / slope);
+        let length = a.hypot(b);
+        if length > 3.0 {
+            tangents[i] = 3.0 * a / length * slope;
+            tangents[i + 1] = 3.0 * b / length * slope;
+        }
+    }
+    let mut segment = 0;
+    #[allow(clippy::cast_precision_loss)]
+    array::from_fn(|i| {
+        let x = i as f32 / 255.0;
+        while segment + 2 < points.len() && x > points[segment + 1].x {
+            segment += 1;
+        }
+        let (p0, p1) = (points[segment], points[segment + 1]);
+        let h = (p1.x - p0.x).max(f32::EPSILON);
+        let t = ((x - p0.x) / h).clamp(0.0, 1.0);
+        // Cubic Hermite basis functions.
+        let t2 = t * t;
+        let h00 = t2.mul_add(2.0f32.mul_add(t, -3.0), 1.0);
+        let h10 = t * (t - 1.0) * (t - 1.0);
+        let h01 = t2 * 2.0f32.mul_add(-t, 3.0);
+        let h11 = t2 * (t - 1.0);
+        let y = h00.mul_add(p0.y, h01.mul_add(p1.y, h * h10.mul_add(tangents[segment], h11 * tangents[segment + 1])));
+        y.clamp(0.0, 1.0)
+    })
+}
+
+/// Window with the settings of the active filter, which is previewed until it is applied or canceled.
+pub struct FilterWindow {
+    pub filter: Option<Filter>,
+    pub adjustment_parameters: AdjustmentParameters,
+    pub convolution_parameters: ConvolutionParameters,
+    /// Whether the preview has to be rendered again, set when the settings change.
+    pub preview_outdated: bool,
+    dragged_point: Option<usize>,
+}
+
+impl FilterWindow {
+    pub fn new() -> Self {
+        Self {
+            filter: None,
+            adjustment_parameters: AdjustmentParameters::default(),
+            convolution_parameters: ConvolutionParameters::default(),
+            preview_outdated: false,
+            dragged_point: None,
+        }
+    }
+
+    pub fn open(&mut self, filter: Filter) {
+        self.filter = Some(filter);
+        self.adjustment_parameters = AdjustmentParameters::default();
+        self.convolution_parameters = ConvolutionParameters::default();
+        self.preview_outdated = true;
+        self.dragged_point = None;
+    }
+
+    /// Returns `Some(true)` if the filter should be applied and `Some(false)` if it should be canceled.
+    pub fn show(&mut self, context: &Context) -> Option<bool> {
+        let filter = self.filter?;
+        let mut open = true;
+        let mut result = None;
+        Window::new(filter.name()).open(&mut open).collapsible(false).resizable(false).show(context, |ui| {
+            match filter {
+                Filter::Adjustment(adjustment) => {
+                    self.preview_outdated |=
+                        adjustment_settings(ui, adjustment, &mut self.adjustment_parameters, &mut self.dragged_point);
+                }
+                Filter::Convolution(convolution) => {
+                    self.preview_outdated |= convolution_settings(ui, convolution, &mut self.convolution_parameters);
+                }
+            }
+            ui.separator();
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("Cancel").on_hover_text("ESC").clicked() {
+                    result = Some(false);
+                }
+                if ui.button("Apply").on_hover_text("ENTER").clicked() {
+                    result = Some(true);
+                }
+            });
+        });
+        if !open {
+            result = Some(false);
+        }
+        result
+    }
+}
+
+/// Returns whether any of the settings changed.
+fn adjustment_settings(
+    ui: &mut Ui,
+    adjustment: Adjustment,
+    parameters: &mut AdjustmentParameters,
+    dragged_point: &mut Option<usize>,
+) -> bool {
+    let mut changed = false;
+    match adjustment {
+        Adjustment::BrightnessContrast => {
+            changed |= ui.add(Slider::new(&mut parameters.brightness, -100.0..=100.0).text("Brightness")).changed();
+            changed |= ui.add(Slider::new(&mut parameters.contrast, -100.0..=100.0).text("Contrast")).changed();
+        }
+        Adjustment::HueSaturation => {
+            changed |= ui.add(Slider::new(&mut parameters.hue, -180.0..=180.0).suffix("°").text("Hue")).changed();
+            changed |= ui.add(Slider::new(&mut parameters.saturation, -100.0..=100.0).text("Saturation")).changed();
+            changed |= ui.add(Slider::new(&mut parameters.lightness, -100.0..=100.0).text("Lightness")).changed();
+        }
+        Adjustment::Levels => {
+            changed |= ui
+                .add(Slider::new(&mut parameters.input_black, 0.0..=parameters.input_white - 1.0).text("Input black"))
+                .changed();
+            changed |= ui
+                .add(Slider::new(&mut parameters.input_white, parameters.input_black + 1.0..=255.0).text("Input white"))
+                .changed();
+            changed |= ui.add(Slider::new(&mut parameters.gamma, 0.1..=10.0).logarithmic(true).text("Gamma")).changed();
+            changed |= ui.add(Slider::new(&mut parameters.output_black, 0.0..=255.0).text("Output black")).changed();
+            changed |= ui.add(Slider::new(&mut parameters.output_white, 0.0..=255.0).text("Output white")).changed();
+        }
+        Adjustment::Curves => {
+            changed |= curve_editor(ui, &mut parameters.curve, dragged_point);
+            ui.label("Click to add a point, drag to move it and right-click to remove it.");
+            if ui.button("Reset").clicked() {
+                parameters.curve = AdjustmentParameters::default().curve;
+                changed = true;
+            }
+        }
+        Adjustment::Invert | Adjustment::Grayscale => {
+            ui.label("This adjustment has no settings.");
+        }
+        Adjustment::Posterize => {
+            changed |= ui.add(Slider::new(&mut parameters.posterize_levels, 2..=64).text("Levels")).changed();
+        }
+        Adjustment::Threshold => {
+            changed |= ui.add(Slider::new(&mut parameters.threshold, 0.0..=255.0).text("Threshold")).changed();
+        }
+    }
+    changed
+}
+
+/// Returns whether any of the settings changed.
+fn convolution_settings(ui: &mut Ui, convolution: Convolution, parameters: &mut ConvolutionParameters) -> bool {
+    let mut changed = false;
+    match convolution {
+        Convolution::GaussianBlur | Convolution::BoxBlur => {
+            changed |= radius_slider(ui, &mut parameters.radius);
+        }
+        Convolution::UnsharpMask => {
+            changed |= radius_slider(ui, &mut parameters.radius);
+            changed |= ui.add(Slider::new(&mut parameters.amount, 0.0..=500.0).suffix("%").text("Amount")).changed();
+            changed |= ui.add(Slider::new(&mut parameters.threshold, 0.0..=255.0).text("Threshold")).changed();
+        }
+        Convolution::EdgeDetection | Convolution::Emboss => {
+            ui.label("This filter has no settings.");
+        }
+        Convolution::Custom => {
+            ui.horizontal(|ui| {
+                ui.label("Kernel size");
+                for size in [3, 5] {
+                    if ui.selectable_label(parameters.kernel_size == size, format!("{size}×{size}")).clicked() {
+                        parameters.kernel_size = size;
+                        parameters.kernel = ConvolutionParameters::identity_kernel(size);
+                        changed = true;
+                    }
+                }
+            });
+            Grid::new("kernel").show(ui, |ui| {
+                for row in parameters.kernel.chunks_mut(5).take(parameters.kernel_size) {
+                    for weight in row.iter_mut().take(parameters.kernel_size) {
+                        changed |= ui.add(DragValue::new(weight).speed(0.1)).changed();
+                    }
+                    ui.end_row();
+                }
+            });
+            changed |= ui
+                .checkbox(&mut parameters.normalize, "Normalize")
+                .on_hover_text("Divide the result by the sum of the weights")
+                .changed();
+            changed |= ui.add(Slider::new(&mut parameters.bias, -1.0..=1.0).text("Bias")).changed();
+        }
+    }
+    changed
+}
+
+fn radius_slider(ui: &mut Ui, radius: &mut u32) -> bool {
+    ui.add(Slider::new(radius, 1..=MAX_RADIUS).logarithmic(true).suffix("px").text("Radius")).changed()
+}
+
+/// Editable curve mapping input values (horizontal) to output values (vertical). Returns whether the points changed.
+fn curve_editor(ui: &mut Ui, points: &mut Vec<Vec2>, dragged_point: &mut Option<usize>) -> bool {
+    let mut changed = false;
+    let (response, painter) =
+        ui.allocate_painter(egui::vec2(CURVE_EDITOR_SIZE, CURVE_EDITOR_SIZE), Sense::click_and_drag());
+    let rect = response.rect;
+    let to_screen = |point: Vec2| {
+        Pos2::new(point.x.mul_add(rect.width(), rect.left()), point.y.mul_add(-rect.height(), rect.bottom()))
+    };
+    let to_curve = |position: Pos2| {
+        Vec2::new((position.x - rect.left()) / rect.width(), (rect.bottom() - position.y) / rect.height())
+            .clamped(Vec2::zero(), Vec2::one())
+    };
+    if let Some(position) = response.interact_pointer_pos() {
+        let closest = points.iter().position(|&point| to_screen(point).distance(position) <= CURVE_POINT_GRAB_DISTANCE);
+        if response.drag_started() {
+            *dragged_point = closest;
+        }
+        if response.clicked() && closest.is_none() {
+            let point = to_curve(position);
+            let index = points.partition_point(|p| p.x < point.x).clamp(1, points.len() - 1);
+            points.insert(index, point);
+            changed = true;
+        }
+        if response.secondary_clicked()
+            && let Some(index) = closest
+            && index != 0
+            && index != points.len() - 1
+        {
+            points.remove(index);
+            changed = true;
+        }
+        if let Some(index) = *dragged_point
+            && response.dragged()
+        {
+            let mut point = to_curve(position);
+            // The edge points move only vertically and the other points stay between their neighbours.
+            point.x = if index == 0 {
+                0.0
+            } else if index == points.len() - 1 {
+                1.0
+            } else {
+                point.x.clamp(points[index - 1].x + 1e-3, points[index + 1].x - 1e-3)
+            };
+            changed |= points[index] != point;
+            points[index] = point;
+        }
+    }
+    if response.drag_stopped() {
+        *dragged_point = None;
+    }
+    let visuals = ui.visuals();
+    painter.rect(rect, 0.0, visuals.extreme_bg_color, visuals.widgets.noninteractive.bg_stroke, StrokeKind::Inside);
+    for i in 1..4 {
+        #[allow(clippy::cast_precision_loss)]
+        let t = i as f32 / 4.0;
+        let stroke = Stroke::new(1.0, visuals.weak_text_color().gamma_multiply(0.3));
+        painter.vline(rect.left() + t * rect.width(), rect.y_range(), stroke);
+        painter.hline(rect.x_range(), rect.top() + t * rect.height(), stroke);
+    }
+    let lookup_table = curve_lookup_table(points);
+    #[allow(clippy::cast_precision_loss)]
+    let line = lookup_table.iter().enumerate().map(|(i, &y)| to_screen(Vec2::new(i as f32 / 255.0, y))).collect();
+    painter.add(Shape::line(line, Stroke::new(2.0, visuals.strong_text_color())));
+    for (i, &point) in points.iter().enumerate() {
+        let color = if *dragged_point == Some(i) { Color32::from_rgb(0, 170, 255) } else { visuals.text_color() };
+        painter.circle_filled(to_screen(point), CURVE_POINT_RADIUS, color);
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_curve() {
+        let lookup_table = curve_lookup_table(&AdjustmentParameters::default().curve);
+        for (i, &y) in lookup_table.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let x = i as f32 / 255.0;
+            assert!((y - x).abs() < 1e-5, "{y} at {x}");
+        }
+    }
+
+    #[test]
+    fn steep_curve_is_clamped_and_monotone() {
+        let points = [Vec2::new(0.0, 0.0), Vec2::new(0.1, 0.9), Vec2::new(0.2, 1.0), Vec2::new(1.0, 1.0)];
+        let lookup_table = curve_lookup_table(&points);
+        assert!(lookup_table.iter().all(|y| (0.0..=1.0).contains(y)));
+        assert!(lookup_table.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(lookup_table[0].abs() < f32::EPSILON);
+        assert!((lookup_table[255] - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn flat_segment_stays_flat() {
+        let points = [Vec2::new(0.0, 0.2), Vec2::new(0.5, 0.2), Vec2::new(1.0, 1.0)];
+        let lookup_table = curve_lookup_table(&points);
+        assert!(lookup_table[..=127].iter().all(|y| (y - 0.2).abs() < 1e-6));
+    }
+
+    #[test]
+    fn inverted_curve() {
+        let lookup_table = curve_lookup_table(&[Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0)]);
+        assert!((lookup_table[0] - 1.0).abs() < f32::EPSILON);
+        assert!(lookup_table[255].abs() < f32::EPSILON);
+        assert!(lookup_table.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+}
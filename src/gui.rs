@@ -1,9 +1,12 @@
-use std::{clone::Clone, mem};
+use std::{clone::Clone, mem, path::Path};
 
+use ab_glyph::FontVec;
 use egui::{
     Button,
+    Checkbox,
     ClippedPrimitive,
     Color32,
+    ComboBox,
     Context,
     DragValue,
     Image,
@@ -14,6 +17,7 @@ use egui::{
     Sides,
     TexturesDelta,
     TopBottomPanel,
+    Ui,
     Window as WindowWidget,
     color_picker::{self, Alpha},
     menu,
@@ -22,14 +26,46 @@ use egui::{
 use egui_wgpu::{Renderer as EguiRenderer, ScreenDescriptor};
 use egui_winit::State;
 use ultraviolet::{Vec2, Vec4};
-use wgpu::{CommandEncoder, LoadOp, Operations, RenderPassColorAttachment, RenderPassDescriptor, StoreOp, TextureView};
+use wgpu::{
+    CommandEncoder,
+    FilterMode,
+    LoadOp,
+    Operations,
+    RenderPassColorAttachment,
+    RenderPassDescriptor,
+    StoreOp,
+    TextureView,
+};
 use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
 
 use crate::{
-    helpers::{Action, Position, Size},
+    filter::{Adjustment, Convolution, Filter, FilterWindow},
+    helpers::{
+        self,
+        Action,
+        AntiAliasing,
+        Arrowhead,
+        HistoryStep,
+        ImageTransform,
+        LineCap,
+        LineJoin,
+        Position,
+        Size,
+        TextAlignment,
+        ZoomPreset,
+    },
+    navigator::Navigator,
+    overlay::Overlay,
+    palette::{Palette, PaletteFormat},
     renderer::{CopyDirection, Renderer},
+    text::{self, FontFamily, TextMask},
 };
 
+const PALETTE_FILE_NAME: &str = "palette.hex";
+const RECENT_COLORS_FILE_NAME: &str = "recent_colors.hex";
+const RECENT_COLORS_CAPACITY: usize = 16;
+pub const VIEW_ROTATION_STEP: f32 = 15.0;
+
 #[allow(clippy::struct_excessive_bools)]
 pub struct Gui {
     context: Context,
@@ -37,27 +73,81 @@ pub struct Gui {
     state: State,
     screen_descriptor: ScreenDescriptor,
     egui_renderer: EguiRenderer,
+    navigator: Navigator,
     paint_jobs: Vec<ClippedPrimitive>,
     textures: TexturesDelta,
     pub using_cursor: bool,
+    pub using_keyboard: bool,
     pub color: Color32,
+    pub secondary_color: Color32,
+    drawing_with_secondary_color: bool,
+    pub right_button_pans: bool,
+    palette: Palette,
+    recent_colors: Palette,
+    pub color_sample_size: u32,
     pub anti_aliasing: bool,
     pub anti_aliasing_scale: f32,
+    pub anti_aliasing_mode: AntiAliasing,
+    pub supersampling: u32,
     pub dashed: bool,
     pub dash_length: f32,
     pub gap_length: f32,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+    pub bezier_degree: u8,
+    pub arrow_start: Arrowhead,
+    pub arrow_end: Arrowhead,
+    pub arrow_size: f32,
+    pub corner_radius: f32,
+    pub sides: u32,
+    pub star: bool,
+    pub star_ratio: f32,
+    pub text: String,
+    font_families: Vec<FontFamily>,
+    font_family: usize,
+    font: Option<(usize, FontVec)>,
+    font_size: f32,
+    bold: bool,
+    italic: bool,
+    text_alignment: TextAlignment,
     pub action: Action,
+    image_transform: Option<ImageTransform>,
+    pub history_step: Option<HistoryStep>,
     pub zoom: f32,
     pub zoom_speed: f32,
     pub offset: Position<f32>,
+    pub view_rotation: f32,
+    pub view_flipped: bool,
     pub preview: bool,
+    pub snap_to_pixel_centers: bool,
+    pub pixel_art: bool,
+    pub linear_filtering: bool,
+    pub nearest_neighbor_threshold: f32,
+    pub grid: bool,
+    pub grid_spacing: f32,
+    pub grid_subdivisions: u32,
+    pub grid_color: Color32,
+    pub pixel_grid: bool,
+    snap_to_grid: bool,
+    overlay: Overlay,
+    filter_window: FilterWindow,
+    pub measuring: bool,
+    cursor_position: Option<Position<f32>>,
+    hovered_color: Option<Color32>,
+    hovered_pixel: Option<Position<u32>>,
+    hovered_pixel_changed: bool, // The color of the hovered pixel is yet to be read back
     pub point_grab_tolerance: f32,
     side_panel_open: bool,
     settings_open: bool,
+    palette_manager_open: bool,
+    palette_path: String,
+    palette_format: PaletteFormat,
+    palette_message: String,
     enable_advanced_settings: bool,
 }
 
 impl Gui {
+    #[allow(clippy::too_many_lines)]
     pub fn new(event_loop: &ActiveEventLoop, renderer: &Renderer) -> Self {
         let context = Context::default();
         context.set_zoom_factor(1.1);
@@ -76,38 +166,108 @@ impl Gui {
             size_in_pixels: [renderer.window_size.width, renderer.window_size.height],
             pixels_per_point: scale_factor * context.zoom_factor(),
         };
-        let egui_renderer = EguiRenderer::new(&renderer.device, renderer.texture_format, None, 1, false);
-        let textures = TexturesDelta::default();
+        let mut egui_renderer = EguiRenderer::new(&renderer.device, renderer.texture_format, None, 1, false);
+        let navigator_texture = egui_renderer.register_native_texture(
+            &renderer.device,
+            &renderer.create_thumbnail_srgb_view(),
+            FilterMode::Linear,
+        );
         Self {
             context,
             scale_factor,
             state,
             screen_descriptor,
             egui_renderer,
+            navigator: Navigator::new(navigator_texture),
             paint_jobs: vec![],
-            textures,
+            textures: TexturesDelta::default(),
             using_cursor: false,
+            using_keyboard: false,
             color: Color32::BLACK,
+            secondary_color: Color32::WHITE,
+            drawing_with_secondary_color: false,
+            right_button_pans: false,
+            palette: Self::load_palette(PALETTE_FILE_NAME).unwrap_or_default(),
+            recent_colors: Self::load_palette(RECENT_COLORS_FILE_NAME).unwrap_or_else(|| Palette {
+                name: "Recent colors".to_owned(),
+                colors: vec![],
+            }),
+            color_sample_size: 1,
             anti_aliasing: true,
             anti_aliasing_scale: 10.0,
+            anti_aliasing_mode: AntiAliasing::Analytic,
+            supersampling: 4,
             dashed: false,
             dash_length: 50.0,
             gap_length: 25.0,
+            line_join: LineJoin::Round,
+            line_cap: LineCap::Round,
+            bezier_degree: 3,
+            arrow_start: Arrowhead::None,
+            arrow_end: Arrowhead::Triangle,
+            arrow_size: 4.0,
+            corner_radius: 0.0,
+            sides: 5,
+            star: false,
+            star_ratio: 50.0,
+            text: String::new(),
+            font_families: FontFamily::all(),
+            font_family: 0,
+            font: None,
+            font_size: 48.0,
+            bold: false,
+            italic: false,
+            text_alignment: TextAlignment::Left,
             action: Action::DrawLine,
+            image_transform: None,
+            history_step: None,
             zoom: 80.0,
             zoom_speed: 100.0,
             offset: Position::new(0.0, 0.0),
+            view_rotation: 0.0,
+            view_flipped: false,
             preview: true,
+            snap_to_pixel_centers: false,
+            pixel_art: false,
+            linear_filtering: true,
+            nearest_neighbor_threshold: 100.0,
+            grid: false,
+            grid_spacing: 50.0,
+            grid_subdivisions: 5,
+            grid_color: Color32::from_rgba_unmultiplied(128, 128, 128, 160),
+            pixel_grid: false,
+            snap_to_grid: false,
+            overlay: Overlay::new(),
+            filter_window: FilterWindow::new(),
+            measuring: false,
+            cursor_position: None,
+            hovered_color: None,
+            hovered_pixel: None,
+            hovered_pixel_changed: false,
             point_grab_tolerance: 10.0,
             side_panel_open: true,
             settings_open: false,
+            palette_manager_open: false,
+            palette_path: String::new(),
+            palette_format: PaletteFormat::Gpl,
+            palette_message: String::new(),
             enable_advanced_settings: false,
         }
     }
 
     pub fn prepare(&mut self, renderer: &mut Renderer) {
+        self.update_hovered_color(renderer);
         let mut options = self.context.options(Clone::clone);
-        let output = self.context.run(self.state.take_egui_input(&renderer.window), |context| {
+        let mut new_color = None;
+        let mut new_secondary_color = None;
+        let mut swap_colors = false;
+        let mut text_changed = false;
+        let mut anti_aliasing_changed = false;
+        let mut zoom_preset = None;
+        let mut filter_result = None;
+        // The context is shared, a clone lets the closure borrow the whole GUI.
+        let context = self.context.clone();
+        let output = context.run(self.state.take_egui_input(&renderer.window), |context| {
             TopBottomPanel::top("bar").show(context, |ui| {
                 menu::bar(ui, |ui| {
                     ui.horizontal_centered(|ui| {
@@ -117,33 +277,58 @@ impl Gui {
                         {
                             self.settings_open = true;
                         }
+                        ui.menu_button("Edit", |ui| self.edit_menu(ui, renderer));
+                        ui.menu_button("View", |ui| zoom_preset = self.view_menu(ui, renderer));
+                        ui.menu_button("Image", |ui| self.image_menu(ui, renderer));
+                        // Shapes in progress would be hidden by the preview, the selection limits the filter.
+                        let enabled = !self.measuring || self.action == Action::CutRectangle;
+                        ui.add_enabled_ui(enabled, |ui| {
+                            ui.menu_button("Adjustments", |ui| {
+                                for adjustment in Adjustment::ALL {
+                                    if ui.button(adjustment.name()).clicked() {
+                                        self.filter_window.open(Filter::Adjustment(adjustment));
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                            ui.menu_button("Filters", |ui| {
+                                for convolution in Convolution::ALL {
+                                    if ui.button(convolution.name()).clicked() {
+                                        self.filter_window.open(Filter::Convolution(convolution));
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        });
                         ui.separator();
-                        for color in [
-                            Color32::BLACK,
-                            Color32::WHITE,
-                            Color32::RED,
-                            Color32::GREEN,
-                            Color32::BLUE,
-                            Color32::YELLOW,
-                            Color32::PURPLE,
-                        ] {
-                            if ui
-                                .add(
-                                    Button::new("")
-                                        .fill(color)
-                                        .min_size(egui::vec2(30.0, 10.0))
-                                        .selected(self.color == color),
-                                )
-                                .clicked()
-                                && self.color != color
-                            {
-                                self.color = color;
-                                if self.action != Action::Erase {
-                                    renderer.compute_uniform_buffer_object.color =
-                                        Vec4::from(self.color.to_normalized_gamma_f32());
-                                    renderer.compute_uniform_buffer_object_changed = true;
+                        ui.add(Button::new("").fill(self.color).min_size(egui::vec2(20.0, 20.0)))
+                            .on_hover_text("Primary color");
+                        ui.add(Button::new("").fill(self.secondary_color).min_size(egui::vec2(20.0, 20.0)))
+                            .on_hover_text("Secondary color");
+                        if ui.button("Swap").on_hover_text("Swap primary and secondary color (X)").clicked() {
+                            swap_colors = true;
+                        }
+                        ui.separator();
+                        ScrollArea::horizontal().max_width(270.0).show(ui, |ui| {
+                            for color in &self.palette.colors {
+                                let response = ui
+                                    .add(
+                                        Button::new("")
+                                            .fill(*color)
+                                            .min_size(egui::vec2(30.0, 10.0))
+                                            .selected(self.color == *color),
+                                    )
+                                    .on_hover_text("Click to set primary, right-click to set secondary color");
+                                if response.clicked() {
+                                    new_color = Some(*color);
+                                }
+                                if response.secondary_clicked() {
+                                    new_secondary_color = Some(*color);
                                 }
                             }
+                        });
+                        if ui.button("Palette").clicked() {
+                            self.palette_manager_open = true;
                         }
                         ui.separator();
                         ui.label("Stroke");
@@ -159,21 +344,11 @@ impl Gui {
                             renderer.compute_uniform_buffer_object_changed = true;
                         }
                         ui.separator();
-                        if ui.checkbox(&mut self.anti_aliasing, "Anti-aliasing").changed() {
-                            if self.anti_aliasing {
-                                if (renderer.compute_uniform_buffer_object.anti_aliasing_scale
-                                    - self.anti_aliasing_scale)
-                                    .abs()
-                                    > f32::EPSILON
-                                {
-                                    renderer.compute_uniform_buffer_object.anti_aliasing_scale =
-                                        self.anti_aliasing_scale * 0.01;
-                                    renderer.compute_uniform_buffer_object_changed = true;
-                                }
-                            } else {
-                                renderer.compute_uniform_buffer_object.anti_aliasing_scale = 0.0;
-                                renderer.compute_uniform_buffer_object_changed = true;
-                            }
+                        if ui
+                            .add_enabled(!self.pixel_art, Checkbox::new(&mut self.anti_aliasing, "Anti-aliasing"))
+                            .changed()
+                        {
+                            anti_aliasing_changed = true;
                         }
                         ui.separator();
                         if ui.checkbox(&mut self.dashed, "Dashed").changed() {
@@ -226,6 +401,7 @@ impl Gui {
                         ui.separator();
                         for (action, image, text) in [
                             (Action::DrawLine, egui::include_image!("icons/light_krita_tool_line.svg"), "Draw line"),
+                            (Action::DrawArrow, egui::include_image!("icons/light_arrow.svg"), "Draw arrow"),
                             (
                                 Action::DrawRectangle,
                                 egui::include_image!("icons/light_krita_tool_rectangle.svg"),
@@ -236,20 +412,37 @@ impl Gui {
                                 egui::include_image!("icons/light_krita_tool_ellipse.svg"),
                                 "Draw circle",
                             ),
-                            // (Action::DrawEllipse, egui::include_image!("icons/ellipse.svg"), "Draw ellipse"),
+                            (
+                                Action::DrawRegularShape,
+                                egui::include_image!("icons/light_regular_shape.svg"),
+                                "Draw regular polygon or star",
+                            ),
+                            (Action::DrawEllipse, egui::include_image!("icons/ellipse.svg"), "Draw ellipse"),
                             (
                                 Action::DrawPolygon,
                                 egui::include_image!("icons/light_krita_tool_polygon.svg"),
                                 "Draw polygon",
                             ),
+                            (Action::DrawPolyline, egui::include_image!("icons/light_polyline.svg"), "Draw polyline"),
+                            (Action::DrawBezier, egui::include_image!("icons/light_bezier.svg"), "Draw Bézier curve"),
+                            (Action::DrawSpline, egui::include_image!("icons/light_spline.svg"), "Draw spline"),
+                            (Action::DrawText, egui::include_image!("icons/light_text.svg"), "Draw text"),
                             (Action::Erase, egui::include_image!("icons/eraser.svg"), "Erase"),
                             (
                                 Action::Fill,
                                 egui::include_image!("icons/light_krita_tool_color_fill.svg"),
                                 "Fill shapes or areas",
                             ),
-                            // (Action::CutRectangle, egui::include_image!("icons/light_tool_rect_selection.svg"), "Cut
-                            // rectangle"),
+                            (
+                                Action::PickColor,
+                                egui::include_image!("icons/light_krita_tool_color_sampler.svg"),
+                                "Pick color (right-click picks the secondary color, hold CTRL to pick with any tool)",
+                            ),
+                            (
+                                Action::CutRectangle,
+                                egui::include_image!("icons/light_tool_rect_selection.svg"),
+                                "Select rectangle",
+                            ),
                         ] {
                             if ui
                                 .add(ImageButton::new(Image::new(image)).selected(self.action == action))
@@ -261,7 +454,12 @@ impl Gui {
                                     renderer.compute_uniform_buffer_object.color = Vec4::one();
                                     renderer.compute_uniform_buffer_object_changed = true;
                                 } else {
-                                    let color = Vec4::from(self.color.to_normalized_gamma_f32());
+                                    let color = if self.drawing_with_secondary_color {
+                                        self.secondary_color
+                                    } else {
+                                        self.color
+                                    };
+                                    let color = Vec4::from(color.to_normalized_gamma_f32());
                                     if renderer.compute_uniform_buffer_object.color != color {
                                         renderer.compute_uniform_buffer_object.color = color;
                                         renderer.compute_uniform_buffer_object_changed = true;
@@ -280,12 +478,46 @@ impl Gui {
                     });
                 });
             });
+            TopBottomPanel::bottom("status bar").show(context, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(position) = self.cursor_position {
+                        ui.label(format!("{:.0}, {:.0}px", position.x.floor(), position.y.floor()));
+                    } else {
+                        ui.label("—");
+                    }
+                    ui.separator();
+                    if let Some(color) = self.hovered_color {
+                        ui.add(Button::new("").fill(color).min_size(egui::vec2(12.0, 12.0)));
+                        let [r, g, b, a] = color.to_srgba_unmultiplied();
+                        ui.label(format!("RGBA({r}, {g}, {b}, {a}) #{r:02X}{g:02X}{b:02X}{a:02X}"));
+                    } else {
+                        ui.label("—");
+                    }
+                    ui.separator();
+                    ui.label(format!("{} × {}px", renderer.texture_size.width, renderer.texture_size.height));
+                    ui.separator();
+                    ui.label(format!("{}%", self.zoom.round()));
+                    ui.separator();
+                    ui.label(self.action.name());
+                    ui.separator();
+                    let points = renderer.storage_buffer_object.points.len();
+                    if points > renderer.max_points {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!("Points: {points} (only {} are drawn)", renderer.max_points),
+                        );
+                    } else {
+                        ui.label(format!("Points: {points}"));
+                    }
+                });
+            });
             SidePanel::left("side panel").resizable(false).show_animated(context, self.side_panel_open, |ui| {
                 ScrollArea::vertical().show(ui, |ui| {
                     ui.add_space(5.0);
                     ui.spacing_mut().slider_width = 244.0;
                     if color_picker::color_picker_color32(ui, &mut self.color, Alpha::OnlyBlend)
                         && self.action != Action::Erase
+                        && !self.drawing_with_secondary_color
                     {
                         renderer.compute_uniform_buffer_object.color = Vec4::from(self.color.to_normalized_gamma_f32());
                         renderer.compute_uniform_buffer_object_changed = true;
@@ -293,24 +525,110 @@ impl Gui {
                     ui.separator();
                     Sides::new().show(
                         ui,
-                        |ui| ui.label("Anti-aliasing scale"),
+                        |ui| ui.label("Recent colors"),
                         |ui| {
-                            if ui
-                                .add(
-                                    DragValue::new(&mut self.anti_aliasing_scale)
-                                        .suffix("%")
-                                        .range(0.0..=100.0)
-                                        .speed(1.0),
-                                )
-                                .changed()
-                                && self.anti_aliasing
-                            {
-                                renderer.compute_uniform_buffer_object.anti_aliasing_scale =
-                                    self.anti_aliasing_scale * 0.01;
-                                renderer.compute_uniform_buffer_object_changed = true;
+                            if ui.button("Clear").clicked() {
+                                self.recent_colors.colors.clear();
                             }
                         },
                     );
+                    ui.horizontal_wrapped(|ui| {
+                        for color in &self.recent_colors.colors {
+                            let response = ui
+                                .add(Button::new("").fill(*color).min_size(egui::vec2(24.0, 24.0)))
+                                .on_hover_text("Click to set primary, right-click to set secondary color");
+                            if response.clicked() {
+                                new_color = Some(*color);
+                            }
+                            if response.secondary_clicked() {
+                                new_secondary_color = Some(*color);
+                            }
+                        }
+                    });
+                    ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Color sample size"),
+                        |ui| {
+                            ComboBox::from_id_salt("color sample size")
+                                .selected_text(format!("{0}×{0}px", self.color_sample_size))
+                                .show_ui(ui, |ui| {
+                                    for size in [1, 3, 5] {
+                                        ui.selectable_value(
+                                            &mut self.color_sample_size,
+                                            size,
+                                            format!("{size}×{size}px"),
+                                        );
+                                    }
+                                });
+                        },
+                    );
+                    ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Anti-aliasing"),
+                        |ui| {
+                            ComboBox::from_id_salt("anti-aliasing")
+                                .selected_text(self.anti_aliasing_mode.name())
+                                .show_ui(ui, |ui| {
+                                    for mode in AntiAliasing::ALL {
+                                        if ui
+                                            .selectable_value(&mut self.anti_aliasing_mode, mode, mode.name())
+                                            .changed()
+                                        {
+                                            anti_aliasing_changed = true;
+                                        }
+                                    }
+                                });
+                        },
+                    );
+                    match self.anti_aliasing_mode {
+                        AntiAliasing::Smoothstep => {
+                            Sides::new().show(
+                                ui,
+                                |ui| ui.label("Anti-aliasing scale"),
+                                |ui| {
+                                    if ui
+                                        .add(
+                                            DragValue::new(&mut self.anti_aliasing_scale)
+                                                .suffix("%")
+                                                .range(0.0..=100.0)
+                                                .speed(1.0),
+                                        )
+                                        .on_hover_text("Width of the transition relative to the stroke")
+                                        .changed()
+                                    {
+                                        anti_aliasing_changed = true;
+                                    }
+                                },
+                            );
+                        }
+                        AntiAliasing::Analytic => {}
+                        AntiAliasing::Supersampling => {
+                            Sides::new().show(
+                                ui,
+                                |ui| ui.label("Samples"),
+                                |ui| {
+                                    ComboBox::from_id_salt("supersampling")
+                                        .selected_text(format!("{0}×{0}", self.supersampling))
+                                        .show_ui(ui, |ui| {
+                                            for samples in [2, 3, 4] {
+                                                if ui
+                                                    .selectable_value(
+                                                        &mut self.supersampling,
+                                                        samples,
+                                                        format!("{samples}×{samples}"),
+                                                    )
+                                                    .changed()
+                                                {
+                                                    anti_aliasing_changed = true;
+                                                }
+                                            }
+                                        });
+                                },
+                            );
+                        }
+                    }
                     ui.separator();
                     Sides::new().show(
                         ui,
@@ -343,11 +661,280 @@ impl Gui {
                         },
                     );
                     ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Line join"),
+                        |ui| {
+                            ComboBox::from_id_salt("line join").selected_text(self.line_join.name()).show_ui(
+                                ui,
+                                |ui| {
+                                    for line_join in LineJoin::ALL {
+                                        if ui
+                                            .selectable_value(&mut self.line_join, line_join, line_join.name())
+                                            .changed()
+                                        {
+                                            renderer.compute_uniform_buffer_object.line_join = self.line_join as u32;
+                                            renderer.compute_uniform_buffer_object_changed = true;
+                                        }
+                                    }
+                                },
+                            );
+                        },
+                    );
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Line cap"),
+                        |ui| {
+                            ComboBox::from_id_salt("line cap").selected_text(self.line_cap.name()).show_ui(ui, |ui| {
+                                for line_cap in LineCap::ALL {
+                                    if ui.selectable_value(&mut self.line_cap, line_cap, line_cap.name()).changed() {
+                                        renderer.compute_uniform_buffer_object.line_cap = self.line_cap as u32;
+                                        renderer.compute_uniform_buffer_object_changed = true;
+                                    }
+                                }
+                            });
+                        },
+                    );
+                    for (label, id_salt, arrowhead, uniform) in [
+                        (
+                            "Arrow start",
+                            "arrow start",
+                            &mut self.arrow_start,
+                            &mut renderer.compute_uniform_buffer_object.arrow_start,
+                        ),
+                        (
+                            "Arrow end",
+                            "arrow end",
+                            &mut self.arrow_end,
+                            &mut renderer.compute_uniform_buffer_object.arrow_end,
+                        ),
+                    ] {
+                        Sides::new().show(
+                            ui,
+                            |ui| ui.label(label),
+                            |ui| {
+                                ComboBox::from_id_salt(id_salt).selected_text(arrowhead.name()).show_ui(ui, |ui| {
+                                    for style in Arrowhead::ALL {
+                                        if ui.selectable_value(arrowhead, style, style.name()).changed() {
+                                            *uniform = style as u32;
+                                            renderer.compute_uniform_buffer_object_changed = true;
+                                        }
+                                    }
+                                });
+                            },
+                        );
+                    }
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Arrow size"),
+                        |ui| {
+                            if ui
+                                .add(DragValue::new(&mut self.arrow_size).suffix("×").range(1.0..=f32::NAN).speed(0.1))
+                                .on_hover_text("Arrowhead length relative to the stroke")
+                                .changed()
+                            {
+                                renderer.compute_uniform_buffer_object.arrow_size = self.arrow_size;
+                                renderer.compute_uniform_buffer_object_changed = true;
+                            }
+                        },
+                    );
+                    ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Corner radius"),
+                        |ui| {
+                            if ui
+                                .add(
+                                    DragValue::new(&mut self.corner_radius)
+                                        .suffix("px")
+                                        .range(0.0..=f32::NAN)
+                                        .speed(1.0),
+                                )
+                                .changed()
+                            {
+                                renderer.compute_uniform_buffer_object.corner_radius = self.corner_radius;
+                                renderer.compute_uniform_buffer_object_changed = true;
+                                renderer.fragment_uniform_buffer_object.corner_radius = self.corner_radius;
+                                renderer.fragment_uniform_buffer_object_changed = true;
+                            }
+                        },
+                    );
+                    ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Sides"),
+                        |ui| {
+                            if ui.add(DragValue::new(&mut self.sides).range(3..=64)).changed() {
+                                renderer.compute_uniform_buffer_object.sides = self.sides;
+                                renderer.compute_uniform_buffer_object_changed = true;
+                                renderer.fragment_uniform_buffer_object.sides = self.sides;
+                                renderer.fragment_uniform_buffer_object_changed = true;
+                            }
+                        },
+                    );
+                    let star_changed = ui.checkbox(&mut self.star, "Star").changed();
+                    let star_ratio_changed = Sides::new()
+                        .show(
+                            ui,
+                            |ui| ui.label("Inner radius"),
+                            |ui| {
+                                ui.add(DragValue::new(&mut self.star_ratio).suffix("%").range(1.0..=100.0).speed(1.0))
+                                    .changed()
+                            },
+                        )
+                        .1;
+                    if star_changed || star_ratio_changed {
+                        let star_ratio = if self.star { self.star_ratio * 0.01 } else { 0.0 };
+                        renderer.compute_uniform_buffer_object.star_ratio = star_ratio;
+                        renderer.compute_uniform_buffer_object_changed = true;
+                        renderer.fragment_uniform_buffer_object.star_ratio = star_ratio;
+                        renderer.fragment_uniform_buffer_object_changed = true;
+                    }
+                    ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Font"),
+                        |ui| {
+                            ComboBox::from_id_salt("font")
+                                .selected_text(
+                                    self.font_families.get(self.font_family).map_or("", |family| family.name.as_str()),
+                                )
+                                .width(160.0)
+                                .show_ui(ui, |ui| {
+                                    for (idx, family) in self.font_families.iter().enumerate() {
+                                        text_changed |=
+                                            ui.selectable_value(&mut self.font_family, idx, &family.name).changed();
+                                    }
+                                });
+                        },
+                    );
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Font size"),
+                        |ui| {
+                            text_changed |= ui
+                                .add(DragValue::new(&mut self.font_size).suffix("px").range(1.0..=f32::NAN).speed(1.0))
+                                .changed();
+                        },
+                    );
+                    ui.horizontal(|ui| {
+                        text_changed |= ui.checkbox(&mut self.bold, "Bold").changed();
+                        text_changed |= ui.checkbox(&mut self.italic, "Italic").changed();
+                    });
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Text alignment"),
+                        |ui| {
+                            ComboBox::from_id_salt("text alignment").selected_text(self.text_alignment.name()).show_ui(
+                                ui,
+                                |ui| {
+                                    for alignment in TextAlignment::ALL {
+                                        text_changed |= ui
+                                            .selectable_value(&mut self.text_alignment, alignment, alignment.name())
+                                            .changed();
+                                    }
+                                },
+                            );
+                        },
+                    );
+                    ui.separator();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Bézier curve degree"),
+                        |ui| {
+                            ComboBox::from_id_salt("bezier degree")
+                                .selected_text(if self.bezier_degree == 2 { "Quadratic" } else { "Cubic" })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.bezier_degree, 2, "Quadratic");
+                                    ui.selectable_value(&mut self.bezier_degree, 3, "Cubic");
+                                });
+                        },
+                    );
+                    ui.separator();
+                    let mut grid_changed = ui.checkbox(&mut self.grid, "Grid").changed();
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Grid spacing"),
+                        |ui| {
+                            grid_changed |= ui
+                                .add(
+                                    DragValue::new(&mut self.grid_spacing)
+                                        .suffix("px")
+                                        .range(2.0..=f32::NAN)
+                                        .speed(1.0),
+                                )
+                                .changed();
+                        },
+                    );
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Grid subdivisions"),
+                        |ui| {
+                            grid_changed |= ui.add(DragValue::new(&mut self.grid_subdivisions).range(1..=32)).changed();
+                        },
+                    );
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Grid color"),
+                        |ui| {
+                            grid_changed |=
+                                color_picker::color_edit_button_srgba(ui, &mut self.grid_color, Alpha::OnlyBlend)
+                                    .changed();
+                        },
+                    );
+                    grid_changed |= ui.checkbox(&mut self.pixel_grid, "Pixel grid").changed();
+                    if grid_changed {
+                        renderer.fragment_uniform_buffer_object.grid_scale =
+                            if self.grid { Vec2::broadcast(self.grid_spacing) } else { Vec2::zero() };
+                        renderer.fragment_uniform_buffer_object.grid_subdivisions = self.grid_subdivisions;
+                        renderer.fragment_uniform_buffer_object.grid_color =
+                            Vec4::from(self.grid_color.to_normalized_gamma_f32());
+                        renderer.fragment_uniform_buffer_object.pixel_grid = u32::from(self.pixel_grid);
+                        renderer.fragment_uniform_buffer_object_changed = true;
+                        renderer.window.request_redraw();
+                    }
+                    ui.checkbox(&mut self.overlay.rulers, "Rulers");
+                    if ui.button("Clear guides").clicked() {
+                        self.overlay.clear_guides();
+                    }
+                    ui.checkbox(&mut self.snap_to_grid, "Snap to grid");
+                    ui.checkbox(&mut self.overlay.snap_to_guides, "Snap to guides");
+                    ui.add_enabled(
+                        !self.pixel_art,
+                        Checkbox::new(&mut self.snap_to_pixel_centers, "Snap to pixel centers"),
+                    );
+                    if ui
+                        .checkbox(&mut self.pixel_art, "Pixel art mode")
+                        .on_hover_text("Snaps control points to pixel centers and draws without anti-aliasing")
+                        .changed()
+                    {
+                        self.anti_aliasing = !self.pixel_art;
+                        anti_aliasing_changed = true;
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.linear_filtering, "Smooth when zoomed out")
+                        .on_hover_text("Displays the canvas with linear filtering and mipmaps below the threshold");
+                    Sides::new().show(
+                        ui,
+                        |ui| ui.label("Nearest-neighbor from"),
+                        |ui| {
+                            ui.add_enabled(
+                                self.linear_filtering,
+                                DragValue::new(&mut self.nearest_neighbor_threshold)
+                                    .suffix("%")
+                                    .range(1.0..=f32::NAN)
+                                    .speed(1.0),
+                            )
+                            .on_hover_text("Magnification of the canvas pixels");
+                        },
+                    );
+                    ui.separator();
                     if ui.checkbox(&mut self.preview, "Preview").changed() {
                         renderer.fragment_uniform_buffer_object.preview = u32::from(self.preview);
                         renderer.fragment_uniform_buffer_object_changed = true;
                         if self.preview {
                             renderer.draw();
+                            self.filter_window.preview_outdated = true;
                         } else {
                             renderer.copy_texture(CopyDirection::BackToFront);
                         }
@@ -374,8 +961,102 @@ impl Gui {
                             );
                         },
                     );
+                    ui.separator();
+                    ui.checkbox(&mut self.right_button_pans, "Pan with right mouse button")
+                        .on_hover_text("Otherwise the right mouse button draws with the secondary color");
                 });
             });
+            self.overlay.show(context, renderer, self.action, self.measuring);
+            filter_result = self.filter_window.show(context);
+            if self.navigator.show(context, renderer, &mut self.zoom) {
+                self.offset.x = renderer.vertex_uniform_buffer_object.offset.x * 100.0;
+                self.offset.y = renderer.vertex_uniform_buffer_object.offset.y * 100.0;
+            }
+            WindowWidget::new("Palette").open(&mut self.palette_manager_open).show(context, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut self.palette.name);
+                });
+                let mut removed_idx = None;
+                ui.horizontal_wrapped(|ui| {
+                    for (idx, color) in self.palette.colors.iter().enumerate() {
+                        let response = ui
+                            .add(
+                                Button::new("")
+                                    .fill(*color)
+                                    .min_size(egui::vec2(24.0, 24.0))
+                                    .selected(self.color == *color),
+                            )
+                            .on_hover_text(color.to_hex());
+                        if response.clicked() {
+                            new_color = Some(*color);
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button("Set as primary color").clicked() {
+                                new_color = Some(*color);
+                                ui.close_menu();
+                            }
+                            if ui.button("Set as secondary color").clicked() {
+                                new_secondary_color = Some(*color);
+                                ui.close_menu();
+                            }
+                            if ui.button("Remove").clicked() {
+                                removed_idx = Some(idx);
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                });
+                if let Some(idx) = removed_idx {
+                    self.palette.colors.remove(idx);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Add primary color").clicked() && !self.palette.colors.contains(&self.color) {
+                        self.palette.colors.push(self.color);
+                    }
+                    if ui.button("Add secondary color").clicked()
+                        && !self.palette.colors.contains(&self.secondary_color)
+                    {
+                        self.palette.colors.push(self.secondary_color);
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.palette.colors.clear();
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("File");
+                    if ui.text_edit_singleline(&mut self.palette_path).changed() {
+                        self.palette_format = PaletteFormat::from_path(Path::new(&self.palette_path));
+                    }
+                });
+                ComboBox::from_label("Format").selected_text(self.palette_format.name()).show_ui(ui, |ui| {
+                    for format in PaletteFormat::ALL {
+                        ui.selectable_value(&mut self.palette_format, format, format.name());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        self.palette_message = match Palette::load(Path::new(&self.palette_path), self.palette_format) {
+                            Ok(palette) => {
+                                self.palette = palette;
+                                format!("Imported {} colors.", self.palette.colors.len())
+                            }
+                            Err(error) => error.to_string(),
+                        };
+                    }
+                    if ui.button("Export").clicked() {
+                        self.palette_message =
+                            match self.palette.save(Path::new(&self.palette_path), self.palette_format) {
+                                Ok(()) => format!("Exported {} colors.", self.palette.colors.len()),
+                                Err(error) => error.to_string(),
+                            };
+                    }
+                });
+                if !self.palette_message.is_empty() {
+                    ui.label(&self.palette_message);
+                }
+            });
             WindowWidget::new("UI Settings").open(&mut self.settings_open).show(context, |ui| {
                 ScrollArea::vertical().show(ui, |ui| {
                     ui.vertical_centered(|ui| {
@@ -410,10 +1091,44 @@ impl Gui {
                 });
             });
         });
+        if let Some(color) = new_color {
+            self.set_color(renderer, color);
+        }
+        if let Some(color) = new_secondary_color {
+            self.set_secondary_color(renderer, color);
+        }
+        if swap_colors {
+            self.swap_colors(renderer);
+        }
+        if text_changed {
+            self.rasterize_text(renderer);
+        }
+        if anti_aliasing_changed {
+            self.update_anti_aliasing(renderer);
+        }
+        if let Some(preset) = zoom_preset {
+            self.apply_zoom_preset(renderer, preset);
+        }
+        match filter_result {
+            Some(true) => self.apply_filter(renderer),
+            Some(false) => self.cancel_filter(renderer),
+            None => {}
+        }
+        renderer.linear_filtering =
+            self.linear_filtering && renderer.magnification() * 100.0 < self.nearest_neighbor_threshold;
         if renderer.compute_uniform_buffer_object_changed {
             renderer.copy_texture(CopyDirection::BackToFront);
             renderer.draw();
             renderer.window.request_redraw();
+            // Restoring the back texture overwrote the preview.
+            self.filter_window.preview_outdated = true;
+        }
+        if self.preview
+            && self.filter_window.preview_outdated
+            && let Some(filter) = self.filter_window.filter
+        {
+            self.filter_window.preview_outdated = false;
+            self.run_filter(renderer, filter);
         }
         self.context.forget_all_images(); // TODO: Do this only if zoom factor changes!
         options.zoom_factor = self.context.zoom_factor();
@@ -424,6 +1139,93 @@ impl Gui {
         self.paint_jobs = self.context.tessellate(output.shapes, self.screen_descriptor.pixels_per_point);
     }
 
+    fn edit_menu(&mut self, ui: &mut Ui, renderer: &Renderer) {
+        for step in HistoryStep::ALL {
+            let enabled = match step {
+                HistoryStep::Undo => renderer.can_undo(),
+                HistoryStep::Redo => renderer.can_redo(),
+            };
+            if ui.add_enabled(enabled, Button::new(step.name()).shortcut_text(step.shortcut())).clicked() {
+                self.history_step = Some(step);
+                ui.close_menu();
+            }
+        }
+    }
+
+    /// Returns the zoom preset to apply.
+    fn view_menu(&mut self, ui: &mut Ui, renderer: &mut Renderer) -> Option<ZoomPreset> {
+        let mut zoom_preset = None;
+        for preset in ZoomPreset::ALL {
+            if ui.add(Button::new(preset.name()).shortcut_text(preset.shortcut())).clicked() {
+                zoom_preset = Some(preset);
+                ui.close_menu();
+            }
+        }
+        ui.separator();
+        let mut view_rotation = None;
+        for (text, shortcut, degrees) in [
+            ("Rotate left", "4", self.view_rotation + VIEW_ROTATION_STEP),
+            ("Rotate right", "6", self.view_rotation - VIEW_ROTATION_STEP),
+            ("Reset rotation", "5", 0.0),
+        ] {
+            if ui.add(Button::new(text).shortcut_text(shortcut)).clicked() {
+                view_rotation = Some(degrees);
+            }
+        }
+        let mut degrees = self.view_rotation;
+        if ui.add(DragValue::new(&mut degrees).prefix("Rotation: ").suffix("°").speed(1.0)).changed() {
+            view_rotation = Some(degrees);
+        }
+        let flipped = ui.add(Checkbox::new(&mut self.view_flipped, "Flip horizontally")).on_hover_text("M").changed();
+        if let Some(degrees) = view_rotation {
+            self.view_rotation = helpers::wrap_degrees(degrees);
+        }
+        if flipped || view_rotation.is_some() {
+            renderer.set_view_transform(self.view_rotation, self.view_flipped);
+            renderer.window.request_redraw();
+        }
+        ui.separator();
+        ui.checkbox(&mut self.navigator.open, "Navigator");
+        zoom_preset
+    }
+
+    fn image_menu(&mut self, ui: &mut Ui, renderer: &Renderer) {
+        for transform in ImageTransform::ALL {
+            if transform == ImageTransform::Crop {
+                ui.separator();
+            }
+            let enabled = transform != ImageTransform::Crop || renderer.selection().is_some();
+            if ui.add_enabled(enabled, Button::new(transform.name())).clicked() {
+                self.image_transform = Some(transform);
+                ui.close_menu();
+            }
+        }
+    }
+
+    /// Applies the image transform and the undo or redo requested since the last frame, which discard the filter
+    /// preview and the shape in progress. Returns whether there were any.
+    pub fn apply_canvas_changes(&mut self, renderer: &mut Renderer) -> bool {
+        let image_transform = self.image_transform.take();
+        let history_step = self.history_step.take();
+        if image_transform.is_none() && history_step.is_none() {
+            return false;
+        }
+        self.cancel_filter(renderer);
+        if let Some(transform) = image_transform {
+            renderer.transform_texture(transform);
+        }
+        match history_step {
+            Some(HistoryStep::Undo) => renderer.undo(),
+            Some(HistoryStep::Redo) => renderer.redo(),
+            None => {}
+        }
+        renderer.storage_buffer_object.points.clear();
+        renderer.storage_buffer_object.length = 0;
+        renderer.storage_buffer_object_changed = true;
+        self.texture_resized(renderer);
+        true
+    }
+
     pub fn render(&mut self, encoder: &mut CommandEncoder, current_texture_view: &TextureView, renderer: &Renderer) {
         for (id, image_delta) in &self.textures.set {
             self.egui_renderer.update_texture(&renderer.device, &renderer.queue, *id, image_delta);
@@ -460,12 +1262,257 @@ impl Gui {
         }
     }
 
+    fn load_palette(file_name: &str) -> Option<Palette> {
+        let path = helpers::config_dir()?.join(file_name);
+        if !path.exists() {
+            return None;
+        }
+        Palette::load(&path, PaletteFormat::from_path(&path))
+            .inspect_err(|error| log::error!("Failed to load {}: {error}", path.display()))
+            .ok()
+    }
+
+    pub fn save_palettes(&self) {
+        let Some(config_dir) = helpers::config_dir() else {
+            return;
+        };
+        for (palette, file_name) in [(&self.palette, PALETTE_FILE_NAME), (&self.recent_colors, RECENT_COLORS_FILE_NAME)]
+        {
+            let path = config_dir.join(file_name);
+            if let Err(error) = palette.save(&path, PaletteFormat::Hex) {
+                log::error!("Failed to save {}: {error}", path.display());
+            }
+        }
+    }
+
+    pub const fn drawing_color(&self) -> Color32 {
+        if self.drawing_with_secondary_color { self.secondary_color } else { self.color }
+    }
+
+    fn update_drawing_color(&self, renderer: &mut Renderer) {
+        if self.action == Action::Erase {
+            return;
+        }
+        let color = Vec4::from(self.drawing_color().to_normalized_gamma_f32());
+        if renderer.compute_uniform_buffer_object.color != color {
+            renderer.compute_uniform_buffer_object.color = color;
+            renderer.compute_uniform_buffer_object_changed = true;
+        }
+    }
+
+    /// Writes the anti-aliasing settings to the compute uniform buffer object, drawing is aliased when it's disabled.
+    pub fn update_anti_aliasing(&self, renderer: &mut Renderer) {
+        let uniform = &mut renderer.compute_uniform_buffer_object;
+        uniform.anti_aliasing = if self.anti_aliasing { self.anti_aliasing_mode as u32 } else { 0 };
+        uniform.anti_aliasing_scale = self.anti_aliasing_scale * 0.01;
+        uniform.samples = self.supersampling;
+        renderer.compute_uniform_buffer_object_changed = true;
+    }
+
+    pub fn set_color(&mut self, renderer: &mut Renderer, color: Color32) {
+        self.color = color;
+        self.update_drawing_color(renderer);
+        renderer.window.request_redraw();
+    }
+
+    pub fn set_secondary_color(&mut self, renderer: &mut Renderer, color: Color32) {
+        self.secondary_color = color;
+        self.update_drawing_color(renderer);
+        renderer.window.request_redraw();
+    }
+
+    pub fn swap_colors(&mut self, renderer: &mut Renderer) {
+        mem::swap(&mut self.color, &mut self.secondary_color);
+        self.update_drawing_color(renderer);
+        renderer.window.request_redraw();
+    }
+
+    /// Selects whether the primitives and fills use the primary or the secondary color.
+    pub fn draw_with_secondary_color(&mut self, renderer: &mut Renderer, secondary: bool) {
+        self.drawing_with_secondary_color = secondary;
+        self.update_drawing_color(renderer);
+    }
+
+    pub fn add_recent_color(&mut self, color: Color32) {
+        self.recent_colors.push_recent(color, RECENT_COLORS_CAPACITY);
+    }
+
+    pub fn pick_color(&mut self, renderer: &mut Renderer, position: Position<f32>, secondary: bool) {
+        if position.x < 0.0 || position.y < 0.0 {
+            return;
+        }
+        // TODO: Implement a trait to convert Position<T> to Position<U>.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let position = Position::new(position.x as u32, position.y as u32);
+        if let Some([r, g, b, a]) = renderer.pick(position, self.color_sample_size / 2) {
+            let color = Color32::from_rgba_unmultiplied(r, g, b, a);
+            if secondary {
+                self.set_secondary_color(renderer, color);
+            } else {
+                self.set_color(renderer, color);
+            }
+        }
+    }
+
+    /// Sets the zoom to the preset and centers the texture.
+    pub fn apply_zoom_preset(&mut self, renderer: &mut Renderer, preset: ZoomPreset) {
+        self.zoom = renderer.preset_zoom(preset);
+        self.offset = Position::new(0.0, 0.0);
+        renderer.scale_texture(self.zoom);
+        renderer.vertex_uniform_buffer_object.offset = Vec2::zero();
+        renderer.vertex_uniform_buffer_object_changed = true;
+        renderer.window.request_redraw();
+    }
+
+    pub const fn filtering(&self) -> bool {
+        self.filter_window.filter.is_some()
+    }
+
+    fn run_filter(&self, renderer: &mut Renderer, filter: Filter) {
+        match filter {
+            Filter::Adjustment(adjustment) => renderer.adjust(adjustment, &self.filter_window.adjustment_parameters),
+            Filter::Convolution(convolution) => {
+                renderer.convolve(convolution, &self.filter_window.convolution_parameters);
+            }
+        }
+    }
+
+    pub fn apply_filter(&mut self, renderer: &mut Renderer) {
+        if let Some(filter) = self.filter_window.filter.take() {
+            self.run_filter(renderer, filter);
+            renderer.copy_texture(CopyDirection::FrontToBack);
+            renderer.window.request_redraw();
+        }
+    }
+
+    pub fn cancel_filter(&mut self, renderer: &mut Renderer) {
+        if self.filter_window.filter.take().is_some() {
+            renderer.copy_texture(CopyDirection::BackToFront);
+            renderer.window.request_redraw();
+        }
+    }
+
+    /// Updates the view and the navigator thumbnail after the texture was recreated.
+    pub fn texture_resized(&mut self, renderer: &mut Renderer) {
+        renderer.scale_texture(self.zoom);
+        self.egui_renderer.update_egui_texture_from_wgpu_texture(
+            &renderer.device,
+            &renderer.create_thumbnail_srgb_view(),
+            FilterMode::Linear,
+            self.navigator.texture,
+        );
+        renderer.window.request_redraw();
+    }
+
+    /// Rotates the view counterclockwise by `degrees`.
+    pub fn rotate_view(&mut self, renderer: &mut Renderer, degrees: f32) {
+        self.view_rotation = helpers::wrap_degrees(self.view_rotation + degrees);
+        renderer.set_view_transform(self.view_rotation, self.view_flipped);
+        renderer.window.request_redraw();
+    }
+
+    pub fn flip_view(&mut self, renderer: &mut Renderer) {
+        self.view_flipped = !self.view_flipped;
+        renderer.set_view_transform(self.view_rotation, self.view_flipped);
+        renderer.window.request_redraw();
+    }
+
+    /// Remembers the canvas position under the cursor and the color of the pixel there for the status bar.
+    pub fn hover(&mut self, renderer: &Renderer, position: Option<Position<f32>>) {
+        self.cursor_position = position.filter(|position| {
+            #[allow(clippy::cast_precision_loss)]
+            let inside = position.x >= 0.0
+                && position.y >= 0.0
+                && position.x < renderer.texture_size.width as f32
+                && position.y < renderer.texture_size.height as f32;
+            inside
+        });
+        // TODO: Implement a trait to convert Position<T> to Position<U>.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let pixel = self.cursor_position.map(|position| Position::new(position.x as u32, position.y as u32));
+        if pixel.is_none() {
+            self.hovered_color = None;
+        }
+        // The color is read back once per frame at most, see `update_hovered_color`.
+        if pixel != self.hovered_pixel {
+            self.hovered_pixel = pixel;
+            self.hovered_pixel_changed = pixel.is_some();
+            renderer.window.request_redraw();
+        }
+    }
+
+    /// Starts reading back the color under the cursor and picks up the previous read back without blocking.
+    fn update_hovered_color(&mut self, renderer: &mut Renderer) {
+        if let Some([r, g, b, a]) = renderer.hovered_color() {
+            self.hovered_color = self.cursor_position.map(|_| Color32::from_rgba_unmultiplied(r, g, b, a));
+        }
+        if self.hovered_pixel_changed
+            && let Some(pixel) = self.hovered_pixel
+        {
+            self.hovered_pixel_changed = !renderer.read_hovered_color(pixel);
+        }
+        if renderer.reading_hovered_color() {
+            renderer.window.request_redraw();
+        }
+    }
+
+    /// Snaps a control point to the grid intersections or to the pixel centers when enabled.
+    pub fn snap(&self, point: Vec2) -> Vec2 {
+        let point = if self.grid && self.snap_to_grid {
+            #[allow(clippy::cast_precision_loss)]
+            helpers::snap_to_grid(point, self.grid_spacing / self.grid_subdivisions as f32)
+        } else if self.snap_to_pixel_centers || self.pixel_art {
+            helpers::snap_to_pixel_center(point)
+        } else {
+            point
+        };
+        self.overlay.snap(point, self.point_grab_tolerance)
+    }
+
+    /// Rasterizes the edited text into the text box starting at the first point.
+    pub fn rasterize_text(&mut self, renderer: &mut Renderer) {
+        if self.action != Action::DrawText || renderer.storage_buffer_object.points.len() < 2 {
+            return;
+        }
+        if self.font.as_ref().is_none_or(|(font_family, _)| *font_family != self.font_family) {
+            self.font = self.font_families.get(self.font_family).and_then(|family| {
+                family
+                    .load()
+                    .inspect_err(|error| log::error!("Failed to load the font {}: {error}", family.name))
+                    .ok()
+                    .map(|font| (self.font_family, font))
+            });
+        }
+        let mask = self.font.as_ref().map_or_else(TextMask::default, |(_, font)| {
+            text::rasterize(font, &self.text, self.font_size, self.bold, self.italic, self.text_alignment)
+        });
+        let position = renderer.storage_buffer_object.points[0];
+        #[allow(clippy::cast_possible_truncation)]
+        renderer.write_text_mask(
+            Position::new(position.x.round() as i32, position.y.round() as i32),
+            mask.size,
+            &mask.coverage,
+        );
+        #[allow(clippy::cast_precision_loss)]
+        {
+            renderer.storage_buffer_object.points[1] =
+                position + Vec2::new(mask.size.width as f32, mask.size.height as f32);
+        }
+        renderer.storage_buffer_object_changed = true;
+        if self.preview {
+            renderer.copy_texture(CopyDirection::BackToFront);
+            renderer.draw();
+        }
+        renderer.window.request_redraw();
+    }
+
     pub fn handle_event(&mut self, window: &Window, event: &WindowEvent) {
         if self.state.on_window_event(window, event).repaint {
             window.request_redraw();
         }
         // Note that context.is_using_pointer returns `false` if the pointer is just hovering over an egui area.
         self.using_cursor = self.context.wants_pointer_input();
+        self.using_keyboard = self.context.wants_keyboard_input();
     }
 
     pub const fn resize(&mut self, size: Size<u32>) {
@@ -1,4 +1,7 @@
+use std::path::PathBuf;
+
 use num_traits::Signed;
+use ultraviolet::Vec2;
 // use ultraviolet::{IVec2, UVec2, Vec2};
 use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
 // use num_traits::AsPrimitive;
@@ -10,6 +13,84 @@ where
     if x.abs() > y.abs() { x } else { y }
 }
 
+// Distance of the rotation handle from the top edge of a rectangle, an ellipse or a selection.
+const ROTATION_HANDLE_DISTANCE: f32 = 30.0;
+
+// Rotation as (cos, sin) of a shape given by its opposite corners and its rotation handle, same as in the shaders.
+pub fn rotation(points: &[Vec2]) -> Vec2 {
+    let direction = points[2] - (points[0] + points[1]) / 2.0;
+    if direction.mag() < 1e-3 {
+        Vec2::unit_x()
+    } else {
+        let direction = direction.normalized();
+        Vec2::new(-direction.y, direction.x)
+    }
+}
+
+pub fn rotation_handle(p1: Vec2, p2: Vec2, rotation: Vec2) -> Vec2 {
+    let diagonal = p2 - p1;
+    let half_height = rotation.x.mul_add(diagonal.y, -rotation.y * diagonal.x).abs() / 2.0;
+    (p1 + p2) / 2.0 + Vec2::new(rotation.y, -rotation.x) * (half_height + ROTATION_HANDLE_DISTANCE)
+}
+
+pub fn snap_rotation(rotation: Vec2, step: f32) -> Vec2 {
+    let step = step.to_radians();
+    let angle = (rotation.y.atan2(rotation.x) / step).round() * step;
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+/// Wraps the angle into the range from -180° to 180°.
+pub fn wrap_degrees(degrees: f32) -> f32 {
+    let degrees = degrees.rem_euclid(360.0);
+    if degrees > 180.0 { degrees - 360.0 } else { degrees }
+}
+
+pub fn snap_to_pixel_center(point: Vec2) -> Vec2 {
+    Vec2::new(point.x.floor() + 0.5, point.y.floor() + 0.5)
+}
+
+pub fn snap_to_grid(point: Vec2, spacing: f32) -> Vec2 {
+    Vec2::new((point.x / spacing).round() * spacing, (point.y / spacing).round() * spacing)
+}
+
+// Constrains `point` dragged away from `anchor`, lines to 45° increments and boxes rotated by `rotation` to squares.
+pub fn constrain(action: Action, anchor: Vec2, point: Vec2, rotation: Vec2) -> Vec2 {
+    let diagonal = point - anchor;
+    match action {
+        Action::DrawLine
+        | Action::DrawArrow
+        | Action::DrawPolygon
+        | Action::DrawPolyline
+        | Action::DrawSpline
+        | Action::DrawBezier => anchor + snap_rotation(diagonal, 45.0) * diagonal.mag(),
+        Action::DrawRectangle | Action::DrawEllipse | Action::CutRectangle => {
+            let local = Vec2::new(
+                rotation.x.mul_add(diagonal.x, rotation.y * diagonal.y),
+                rotation.x.mul_add(diagonal.y, -rotation.y * diagonal.x),
+            );
+            let side = local.x.abs().max(local.y.abs());
+            let local = Vec2::new(side.copysign(local.x), side.copysign(local.y));
+            anchor
+                + Vec2::new(
+                    rotation.x.mul_add(local.x, -rotation.y * local.y),
+                    rotation.y.mul_add(local.x, rotation.x * local.y),
+                )
+        }
+        _ => point,
+    }
+}
+
+pub const fn draws_from_center(action: Action) -> bool {
+    matches!(
+        action,
+        Action::DrawLine | Action::DrawArrow | Action::DrawRectangle | Action::DrawEllipse | Action::CutRectangle
+    )
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|config_dir| config_dir.join(env!("CARGO_PKG_NAME")))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
 pub enum Action {
@@ -17,13 +98,228 @@ pub enum Action {
     DrawLine,
     DrawRectangle,
     DrawCircle,
-    #[allow(dead_code)]
     DrawEllipse,
     DrawPolygon,
     Erase,
     Fill,
-    #[allow(dead_code)]
     CutRectangle,
+    PickColor,
+    DrawPolyline,
+    DrawBezier,
+    DrawSpline,
+    DrawArrow,
+    DrawRegularShape,
+    DrawText,
+}
+
+impl Action {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Init => "None",
+            Self::DrawLine => "Line",
+            Self::DrawRectangle => "Rectangle",
+            Self::DrawCircle => "Circle",
+            Self::DrawEllipse => "Ellipse",
+            Self::DrawPolygon => "Polygon",
+            Self::Erase => "Eraser",
+            Self::Fill => "Fill",
+            Self::CutRectangle => "Rectangle selection",
+            Self::PickColor => "Color picker",
+            Self::DrawPolyline => "Polyline",
+            Self::DrawBezier => "Bézier curve",
+            Self::DrawSpline => "Spline",
+            Self::DrawArrow => "Arrow",
+            Self::DrawRegularShape => "Regular shape",
+            Self::DrawText => "Text",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    pub const ALL: [Self; 3] = [Self::Miter, Self::Round, Self::Bevel];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Miter => "Miter",
+            Self::Round => "Round",
+            Self::Bevel => "Bevel",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    pub const ALL: [Self; 3] = [Self::Butt, Self::Round, Self::Square];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Butt => "Butt",
+            Self::Round => "Round",
+            Self::Square => "Square",
+        }
+    }
+}
+
+// Zero is reserved for drawing without anti-aliasing.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum AntiAliasing {
+    Smoothstep = 1,
+    Analytic,
+    Supersampling,
+}
+
+impl AntiAliasing {
+    pub const ALL: [Self; 3] = [Self::Smoothstep, Self::Analytic, Self::Supersampling];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Smoothstep => "Smoothstep",
+            Self::Analytic => "Analytic",
+            Self::Supersampling => "Supersampling",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Arrowhead {
+    None,
+    Open,
+    Triangle,
+    Circle,
+    Bar,
+}
+
+impl Arrowhead {
+    pub const ALL: [Self; 5] = [Self::None, Self::Open, Self::Triangle, Self::Circle, Self::Bar];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Open => "Open",
+            Self::Triangle => "Triangle",
+            Self::Circle => "Circle",
+            Self::Bar => "Bar",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlignment {
+    pub const ALL: [Self; 3] = [Self::Left, Self::Center, Self::Right];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Left => "Left",
+            Self::Center => "Center",
+            Self::Right => "Right",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ZoomPreset {
+    Fit,
+    ActualPixels,
+    Fill,
+}
+
+impl ZoomPreset {
+    pub const ALL: [Self; 3] = [Self::Fit, Self::ActualPixels, Self::Fill];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Fit => "Fit to window",
+            Self::ActualPixels => "Actual pixels (100%)",
+            Self::Fill => "Fill window",
+        }
+    }
+
+    pub const fn shortcut(self) -> &'static str {
+        match self {
+            Self::Fit => "Ctrl+0",
+            Self::ActualPixels => "Ctrl+1",
+            Self::Fill => "Ctrl+2",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HistoryStep {
+    Undo,
+    Redo,
+}
+
+impl HistoryStep {
+    pub const ALL: [Self; 2] = [Self::Undo, Self::Redo];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+        }
+    }
+
+    pub const fn shortcut(self) -> &'static str {
+        match self {
+            Self::Undo => "Ctrl+Z",
+            Self::Redo => "Ctrl+Y",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageTransform {
+    FlipHorizontal,
+    FlipVertical,
+    RotateClockwise,
+    RotateCounterclockwise,
+    Rotate180,
+    Crop,
+}
+
+impl ImageTransform {
+    pub const ALL: [Self; 6] = [
+        Self::FlipHorizontal,
+        Self::FlipVertical,
+        Self::RotateClockwise,
+        Self::RotateCounterclockwise,
+        Self::Rotate180,
+        Self::Crop,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::FlipHorizontal => "Flip horizontally",
+            Self::FlipVertical => "Flip vertically",
+            Self::RotateClockwise => "Rotate 90° clockwise",
+            Self::RotateCounterclockwise => "Rotate 90° counterclockwise",
+            Self::Rotate180 => "Rotate 180°",
+            Self::Crop => "Crop to selection",
+        }
+    }
 }
 
 // macro_rules! impl_from_vec {
@@ -87,7 +383,7 @@ where
 // impl_from_vec!(UVec2, Size<u32>);
 // impl_from_vec!(Vec2, Size<f32>);
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct Position<T> {
     pub x: T,
     pub y: T,
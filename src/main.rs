@@ -1,12 +1,20 @@
 #![cfg_attr(all(target_os = "windows", not(debug_assertions)), windows_subsystem = "windows")]
 
+mod color;
+mod filter;
 mod gui;
 mod helpers;
+mod navigator;
+mod overlay;
+mod palette;
 mod renderer;
-use std::sync::Arc;
+mod text;
+mod tiling;
+use std::{env, sync::Arc};
 
-use gui::Gui;
-use helpers::{Action, Position, Size, abs_max};
+use color::CanvasFormat;
+use gui::{Gui, VIEW_ROTATION_STEP};
+use helpers::{Action, HistoryStep, Position, Size, ZoomPreset, abs_max};
 use renderer::{CopyDirection, Renderer};
 use ultraviolet::{Vec2, Vec4};
 use winit::{
@@ -14,7 +22,7 @@ use winit::{
     dpi::{LogicalSize, PhysicalPosition},
     event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
+    keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey},
     window::{Window, WindowId},
 };
 
@@ -32,10 +40,76 @@ struct App {
     gui: Option<Gui>,
     absolute_position: Position<f32>,
     position: Position<f32>,
+    anchor: Vec2, // Where the current shape was started
+    modifiers: ModifiersState,
     state: State,
+    picking_color: Option<bool>, // Whether the secondary color is being picked
+    space_pressed: bool,
     grabbed_point_idx: Option<usize>,
-    grab_position: Option<Position<f32>>,
-    grab_offset: Option<Vec2>,
+    grab_position: Option<Position<f32>>, // Canvas position kept under the cursor while panning
+}
+
+impl App {
+    /// Rotates the view with 4 and 6, resets the rotation with 5 and flips the view with M.
+    fn transform_view(&mut self, code: KeyCode) {
+        #[allow(clippy::unwrap_used)]
+        let renderer = self.renderer.as_mut().unwrap();
+        #[allow(clippy::unwrap_used)]
+        let gui = self.gui.as_mut().unwrap();
+        match code {
+            KeyCode::Digit4 | KeyCode::Numpad4 => gui.rotate_view(renderer, VIEW_ROTATION_STEP),
+            KeyCode::Digit6 | KeyCode::Numpad6 => gui.rotate_view(renderer, -VIEW_ROTATION_STEP),
+            KeyCode::Digit5 | KeyCode::Numpad5 => gui.rotate_view(renderer, -gui.view_rotation),
+            _ => gui.flip_view(renderer),
+        }
+        self.position = renderer.cursor_absolute_to_relative(self.absolute_position);
+        gui.hover(renderer, Some(self.position));
+    }
+
+    /// Moves the last point of the shape being added to the cursor, snapped and constrained the same way on every
+    /// path that moves the cursor over the canvas. The eraser erases up to the cursor instead.
+    fn move_last_point(&mut self) {
+        #[allow(clippy::unwrap_used)]
+        let renderer = self.renderer.as_mut().unwrap();
+        #[allow(clippy::unwrap_used)]
+        let gui = self.gui.as_mut().unwrap();
+        if gui.using_cursor || gui.action == Action::DrawText {
+            return;
+        }
+        if gui.action == Action::Erase {
+            renderer.storage_buffer_object.points.swap_remove(0);
+            renderer.storage_buffer_object.points.push(Vec2::new(self.position.x, self.position.y));
+            renderer.storage_buffer_object_changed = true;
+            // The stroke is committed as a whole when the button is released, so that it's undone in one step.
+            renderer.draw();
+        } else {
+            let mut point = gui.snap(Vec2::new(self.position.x, self.position.y));
+            let points = &mut renderer.storage_buffer_object.points;
+            points.pop();
+            // The second point is constrained relative to the anchor, also when it's the center.
+            if self.modifiers.shift_key() {
+                let anchor = if points.len() == 1 { self.anchor } else { points[points.len() - 1] };
+                point = helpers::constrain(gui.action, anchor, point, Vec2::unit_x());
+            }
+            // The first point mirrors the cursor around the anchor when drawing from the center.
+            if points.len() == 1 {
+                points[0] = if helpers::draws_from_center(gui.action)
+                    && (self.modifiers.alt_key() || self.modifiers.control_key())
+                {
+                    self.anchor * 2.0 - point
+                } else {
+                    self.anchor
+                };
+            }
+            points.push(point);
+            renderer.storage_buffer_object_changed = true;
+            if gui.preview {
+                renderer.copy_texture(CopyDirection::BackToFront);
+                renderer.draw();
+            }
+        }
+        renderer.window.request_redraw();
+    }
 }
 
 // TODO: Clean-up and remove unwrap().
@@ -48,7 +122,13 @@ impl ApplicationHandler for App {
             .with_position(PhysicalPosition::new(0, 0))
             .with_maximized(true);
         let window = Arc::new(event_loop.create_window(window_attributes).expect("Failed to create window"));
-        let renderer = pollster::block_on(Renderer::new(window.clone(), Size::new(1500, 1000)))
+        // The 8-bit canvas uses half as much memory, the renderer falls back to it on devices without the 16-bit one.
+        let canvas_format = if env::args().any(|argument| argument == "--low-precision") {
+            CanvasFormat::Rgba8
+        } else {
+            CanvasFormat::Rgba16Float
+        };
+        let renderer = pollster::block_on(Renderer::new(window.clone(), Size::new(1500, 1000), canvas_format))
             .expect("Failed to create rendering backend");
         let gui = Gui::new(event_loop, &renderer);
         self.renderer = Some(renderer);
@@ -62,13 +142,22 @@ impl ApplicationHandler for App {
         renderer.compute_uniform_buffer_object.color = Vec4::one();
         renderer.compute_uniform_buffer_object.action = Action::Init as u32;
         renderer.compute_uniform_buffer_object.stroke = 10.0;
-        renderer.compute_uniform_buffer_object.anti_aliasing_scale =
-            if gui.anti_aliasing { gui.anti_aliasing_scale * 0.01 } else { 0.0 };
+        gui.update_anti_aliasing(renderer);
         renderer.compute_uniform_buffer_object.dash_length = if gui.dashed { gui.dash_length } else { 0.0 };
         renderer.compute_uniform_buffer_object.gap_length = if gui.dashed { gui.gap_length } else { 0.0 };
+        renderer.compute_uniform_buffer_object.line_join = gui.line_join as u32;
+        renderer.compute_uniform_buffer_object.line_cap = gui.line_cap as u32;
+        renderer.compute_uniform_buffer_object.arrow_start = gui.arrow_start as u32;
+        renderer.compute_uniform_buffer_object.arrow_end = gui.arrow_end as u32;
+        renderer.compute_uniform_buffer_object.arrow_size = gui.arrow_size;
+        renderer.compute_uniform_buffer_object.corner_radius = gui.corner_radius;
+        renderer.compute_uniform_buffer_object.sides = gui.sides;
+        renderer.compute_uniform_buffer_object.star_ratio = if gui.star { gui.star_ratio * 0.01 } else { 0.0 };
         renderer.compute_uniform_buffer_object_changed = true;
         renderer.draw();
         renderer.copy_texture(CopyDirection::FrontToBack);
+        // Clearing the canvas isn't a change that can be undone.
+        renderer.clear_history();
         renderer.compute_uniform_buffer_object.color = Vec4::from(gui.color.to_normalized_gamma_f32());
         renderer.compute_uniform_buffer_object.action = gui.action as u32;
         renderer.compute_uniform_buffer_object_changed = true;
@@ -76,9 +165,16 @@ impl ApplicationHandler for App {
         renderer.scale_texture(gui.zoom);
         renderer.vertex_uniform_buffer_object.offset = Vec2::new(gui.offset.x, gui.offset.y) * 0.01;
         renderer.vertex_uniform_buffer_object_changed = true;
-        renderer.fragment_uniform_buffer_object.grid_scale = Vec2::zero(); // TODO: Change when GUI widget gets added.
+        renderer.fragment_uniform_buffer_object.grid_scale =
+            if gui.grid { Vec2::broadcast(gui.grid_spacing) } else { Vec2::zero() };
+        renderer.fragment_uniform_buffer_object.grid_subdivisions = gui.grid_subdivisions;
+        renderer.fragment_uniform_buffer_object.grid_color = Vec4::from(gui.grid_color.to_normalized_gamma_f32());
+        renderer.fragment_uniform_buffer_object.pixel_grid = u32::from(gui.pixel_grid);
         renderer.fragment_uniform_buffer_object.action = gui.action as u32;
         renderer.fragment_uniform_buffer_object.preview = u32::from(gui.preview);
+        renderer.fragment_uniform_buffer_object.corner_radius = gui.corner_radius;
+        renderer.fragment_uniform_buffer_object.sides = gui.sides;
+        renderer.fragment_uniform_buffer_object.star_ratio = renderer.compute_uniform_buffer_object.star_ratio;
         renderer.fragment_uniform_buffer_object_changed = true;
         window.request_redraw();
     }
@@ -90,9 +186,18 @@ impl ApplicationHandler for App {
         let gui = self.gui.as_mut().unwrap();
         gui.handle_event(&renderer.window, &event);
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::CloseRequested => {
+                gui.save_palettes();
+                event_loop.exit();
+            }
             WindowEvent::RedrawRequested => {
+                gui.measuring = matches!(self.state, State::AddPoints | State::EditPoints);
                 gui.prepare(renderer); // TODO: Is it necessaty to call this every redraw request event?
+                // The shape in progress is discarded.
+                if gui.apply_canvas_changes(renderer) {
+                    self.grabbed_point_idx = None;
+                    self.state = State::Init;
+                }
                 renderer
                     .render_with(|encoder, current_texture_view, renderer| {
                         gui.render(encoder, current_texture_view, renderer);
@@ -110,57 +215,60 @@ impl ApplicationHandler for App {
                 // TODO: Implement a trait to convert Position<T> to Position<U>.
                 self.absolute_position = Position::new(position.x as f32, position.y as f32);
                 self.position = renderer.cursor_absolute_to_relative(self.absolute_position);
+                gui.hover(renderer, Some(self.position));
                 if let Some(grab_position) = self.grab_position {
-                    if let Some(grab_offset) = self.grab_offset {
-                        // TODO: Simplify
-                        let x = if renderer.vertex_uniform_buffer_object.scale.x
-                            < renderer.vertex_uniform_buffer_object.scale.y
-                        {
-                            renderer.texture_size.width
-                        } else {
-                            renderer.texture_size.height
-                        } as f32;
-                        renderer.vertex_uniform_buffer_object.offset = grab_offset;
-                        renderer.vertex_uniform_buffer_object.offset.x += (self.absolute_position.x - grab_position.x)
-                            * 0.001
-                            / (renderer.texture_size.width as f32 / x)
-                            / renderer.vertex_uniform_buffer_object.scale.x;
-                        renderer.vertex_uniform_buffer_object.offset.y += (grab_position.y - self.absolute_position.y)
-                            * 0.001
-                            / (renderer.texture_size.height as f32 / x)
-                            / renderer.vertex_uniform_buffer_object.scale.y;
-                        renderer.vertex_uniform_buffer_object_changed = true;
-                        gui.offset.x = renderer.vertex_uniform_buffer_object.offset.x * 100.0;
-                        gui.offset.y = renderer.vertex_uniform_buffer_object.offset.y * 100.0;
-                        renderer.window.request_redraw();
-                    }
+                    renderer.pan_to(grab_position, self.absolute_position);
+                    gui.offset.x = renderer.vertex_uniform_buffer_object.offset.x * 100.0;
+                    gui.offset.y = renderer.vertex_uniform_buffer_object.offset.y * 100.0;
+                    self.position = grab_position;
+                    renderer.window.request_redraw();
+                }
+                if let Some(secondary) = self.picking_color
+                    && !gui.using_cursor
+                {
+                    gui.pick_color(renderer, self.position, secondary);
                 }
                 match self.state {
-                    State::AddPoints => {
-                        if !gui.using_cursor {
-                            if gui.action == Action::Erase {
-                                renderer.storage_buffer_object.points.swap_remove(0);
-                                renderer.storage_buffer_object.points.push(Vec2::new(self.position.x, self.position.y));
-                                renderer.storage_buffer_object_changed = true;
-                                renderer.draw();
-                                renderer.copy_texture(CopyDirection::FrontToBack);
-                            } else {
-                                renderer.storage_buffer_object.points.pop();
-                                renderer.storage_buffer_object.points.push(Vec2::new(self.position.x, self.position.y));
-                                renderer.storage_buffer_object_changed = true;
-                                if gui.preview {
-                                    renderer.copy_texture(CopyDirection::BackToFront);
-                                    renderer.draw();
-                                }
-                            }
-                            renderer.window.request_redraw();
-                        }
-                    }
+                    State::AddPoints => self.move_last_point(),
                     State::EditPoints => {
                         if let Some(grabbed_point_idx) = self.grabbed_point_idx {
                             if !gui.using_cursor {
-                                renderer.storage_buffer_object.points[grabbed_point_idx] =
-                                    Vec2::new(self.position.x, self.position.y);
+                                let points = &mut renderer.storage_buffer_object.points;
+                                let rotatable = matches!(
+                                    gui.action,
+                                    Action::DrawRectangle | Action::DrawEllipse | Action::CutRectangle
+                                ) && points.len() == 3;
+                                let rotation = if rotatable { helpers::rotation(points) } else { Vec2::unit_x() };
+                                let mut point = gui.snap(Vec2::new(self.position.x, self.position.y));
+                                if grabbed_point_idx < 2 && points.len() > 1 {
+                                    let from_center = helpers::draws_from_center(gui.action)
+                                        && (self.modifiers.alt_key() || self.modifiers.control_key());
+                                    let other_idx = if grabbed_point_idx == 0 { 1 } else { grabbed_point_idx - 1 };
+                                    let anchor =
+                                        if from_center { (points[0] + points[1]) / 2.0 } else { points[other_idx] };
+                                    if self.modifiers.shift_key() {
+                                        point = helpers::constrain(gui.action, anchor, point, rotation);
+                                    }
+                                    if from_center {
+                                        points[other_idx] = anchor * 2.0 - point;
+                                    }
+                                } else if grabbed_point_idx >= 2 && !rotatable && self.modifiers.shift_key() {
+                                    point =
+                                        helpers::constrain(gui.action, points[grabbed_point_idx - 1], point, rotation);
+                                }
+                                points[grabbed_point_idx] = point;
+                                if rotatable {
+                                    // Keep the rotation while resizing and snap it to 15° with SHIFT while rotating.
+                                    if grabbed_point_idx != 2 {
+                                        points[2] = helpers::rotation_handle(points[0], points[1], rotation);
+                                    } else if self.modifiers.shift_key() {
+                                        points[2] = helpers::rotation_handle(
+                                            points[0],
+                                            points[1],
+                                            helpers::snap_rotation(helpers::rotation(points), 15.0),
+                                        );
+                                    }
+                                }
                                 renderer.storage_buffer_object_changed = true;
                                 if gui.preview {
                                     renderer.copy_texture(CopyDirection::BackToFront);
@@ -178,102 +286,149 @@ impl ApplicationHandler for App {
                 state,
                 button,
             } => {
-                match button {
-                    MouseButton::Left => {
-                        match state {
-                            ElementState::Pressed => {
-                                if gui.using_cursor {
-                                    return;
+                // The right mouse button either draws with the secondary color or pans like the middle mouse button.
+                let pan = match button {
+                    MouseButton::Left => self.space_pressed,
+                    MouseButton::Right => gui.right_button_pans,
+                    MouseButton::Middle => true,
+                    _ => return,
+                };
+                // Releasing any button ends the pan, SPACE or the setting may have changed since it started.
+                if state == ElementState::Released {
+                    self.grab_position = None;
+                }
+                if pan {
+                    if state == ElementState::Pressed {
+                        self.grab_position = if gui.using_cursor { None } else { Some(self.position) };
+                    }
+                    return;
+                }
+                let secondary = button == MouseButton::Right;
+                match state {
+                    ElementState::Pressed => {
+                        // The canvas is locked while a filter is previewed.
+                        if gui.using_cursor || gui.filtering() {
+                            return;
+                        }
+                        // CTRL alone picks a color before a shape is started, combined with SHIFT or ALT and while
+                        // placing or editing a shape it's a drawing constraint.
+                        let pick_modifier =
+                            self.modifiers.control_key() && !self.modifiers.shift_key() && !self.modifiers.alt_key();
+                        if gui.action == Action::PickColor || (pick_modifier && matches!(self.state, State::Init)) {
+                            self.picking_color = Some(secondary);
+                            gui.pick_color(renderer, self.position, secondary);
+                            return;
+                        }
+                        if matches!(self.state, State::Init) {
+                            gui.draw_with_secondary_color(renderer, secondary);
+                        }
+                        if gui.action == Action::Fill {
+                            gui.add_recent_color(gui.drawing_color());
+                            // TODO: Implement a trait to convert Position<T> to Position<U>.
+                            renderer.fill(
+                                Position::new(self.position.x as u32, self.position.y as u32),
+                                gui.drawing_color().to_srgba_unmultiplied(),
+                            );
+                            renderer.copy_texture(CopyDirection::FrontToBack);
+                            renderer.window.request_redraw();
+                            return;
+                        }
+                        match self.state {
+                            State::Init => {
+                                self.anchor = Vec2::new(self.position.x, self.position.y);
+                                if gui.action != Action::Erase {
+                                    self.anchor = gui.snap(self.anchor);
                                 }
-                                if gui.action == Action::Fill {
-                                    // TODO: Implement a trait to convert Position<T> to Position<U>.
-                                    renderer.fill(
-                                        Position::new(self.position.x as u32, self.position.y as u32),
-                                        gui.color.to_array(),
-                                    );
-                                    renderer.copy_texture(CopyDirection::FrontToBack);
-                                    renderer.window.request_redraw();
-                                    return;
+                                renderer.storage_buffer_object.points.clear();
+                                renderer.storage_buffer_object.points.push(self.anchor);
+                                renderer.storage_buffer_object.points.push(self.anchor);
+                                renderer.storage_buffer_object.length = 2;
+                                renderer.storage_buffer_object_changed = true;
+                                if gui.action != Action::Erase {
+                                    gui.add_recent_color(gui.drawing_color());
                                 }
-                                match self.state {
-                                    State::Init => {
-                                        renderer.storage_buffer_object.points.clear();
-                                        renderer
-                                            .storage_buffer_object
-                                            .points
-                                            .push(Vec2::new(self.position.x, self.position.y));
-                                        renderer
-                                            .storage_buffer_object
-                                            .points
-                                            .push(Vec2::new(self.position.x, self.position.y));
-                                        renderer.storage_buffer_object.length = 2;
-                                        renderer.storage_buffer_object_changed = true;
-                                        self.state = State::AddPoints;
-                                    }
-                                    State::AddPoints => match gui.action {
-                                        Action::DrawLine
-                                        | Action::DrawRectangle
-                                        | Action::DrawCircle
-                                        | Action::DrawEllipse
-                                        | Action::CutRectangle => {
-                                            self.state = State::EditPoints;
-                                        }
-                                        Action::DrawPolygon => {
-                                            renderer
-                                                .storage_buffer_object
-                                                .points
-                                                .push(Vec2::new(self.position.x, self.position.y));
-                                            renderer.storage_buffer_object.length += 1;
-                                            renderer.storage_buffer_object_changed = true;
-                                        }
-                                        _ => {}
-                                    },
-                                    State::EditPoints => match self.grabbed_point_idx {
-                                        Some(_) => self.grabbed_point_idx = None,
-                                        None => {
-                                            self.grabbed_point_idx = renderer
-                                                .storage_buffer_object
-                                                .points
-                                                .iter()
-                                                .enumerate()
-                                                .filter_map(|(idx, point)| {
-                                                    let dx = (point.x - self.position.x).abs();
-                                                    let dy = (point.y - self.position.y).abs();
-                                                    if dx < gui.point_grab_tolerance && dy < gui.point_grab_tolerance {
-                                                        Some((idx, dx + dy))
-                                                    } else {
-                                                        None
-                                                    }
-                                                })
-                                                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
-                                                .map(|(idx, _)| idx);
-                                        }
-                                    },
+                                self.state = State::AddPoints;
+                                if gui.action == Action::DrawText {
+                                    gui.text.clear();
+                                    gui.rasterize_text(renderer);
                                 }
-                                // renderer.window.request_redraw();
                             }
-                            ElementState::Released => {
-                                if gui.action == Action::Erase {
-                                    self.state = State::Init;
+                            State::AddPoints => match gui.action {
+                                Action::DrawLine
+                                | Action::DrawCircle
+                                | Action::DrawArrow
+                                | Action::DrawRegularShape => {
+                                    self.state = State::EditPoints;
                                 }
-                            }
+                                Action::DrawRectangle | Action::DrawEllipse | Action::CutRectangle => {
+                                    let points = &mut renderer.storage_buffer_object.points;
+                                    let rotation_handle =
+                                        helpers::rotation_handle(points[0], points[1], Vec2::unit_x());
+                                    points.push(rotation_handle);
+                                    renderer.storage_buffer_object.length = 3;
+                                    renderer.storage_buffer_object_changed = true;
+                                    self.state = State::EditPoints;
+                                }
+                                Action::DrawText => {
+                                    renderer.storage_buffer_object.points[0] =
+                                        Vec2::new(self.position.x, self.position.y);
+                                    gui.rasterize_text(renderer);
+                                }
+                                Action::DrawBezier => {
+                                    // Control points evenly spaced on the line between the end points.
+                                    let points = &mut renderer.storage_buffer_object.points;
+                                    let (start, end) = (points[0], points[1]);
+                                    for i in 1..gui.bezier_degree {
+                                        points.insert(
+                                            usize::from(i),
+                                            start + (end - start) * f32::from(i) / f32::from(gui.bezier_degree),
+                                        );
+                                    }
+                                    renderer.storage_buffer_object.length = u32::from(gui.bezier_degree) + 1;
+                                    renderer.storage_buffer_object_changed = true;
+                                    self.state = State::EditPoints;
+                                }
+                                Action::DrawPolygon | Action::DrawPolyline | Action::DrawSpline => {
+                                    renderer
+                                        .storage_buffer_object
+                                        .points
+                                        .push(Vec2::new(self.position.x, self.position.y));
+                                    renderer.storage_buffer_object.length += 1;
+                                    renderer.storage_buffer_object_changed = true;
+                                }
+                                _ => {}
+                            },
+                            State::EditPoints => match self.grabbed_point_idx {
+                                Some(_) => self.grabbed_point_idx = None,
+                                None => {
+                                    self.grabbed_point_idx = renderer
+                                        .storage_buffer_object
+                                        .points
+                                        .iter()
+                                        .enumerate()
+                                        .filter_map(|(idx, point)| {
+                                            let dx = (point.x - self.position.x).abs();
+                                            let dy = (point.y - self.position.y).abs();
+                                            if dx < gui.point_grab_tolerance && dy < gui.point_grab_tolerance {
+                                                Some((idx, dx + dy))
+                                            } else {
+                                                None
+                                            }
+                                        })
+                                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                                        .map(|(idx, _)| idx);
+                                }
+                            },
                         }
+                        // renderer.window.request_redraw();
                     }
-                    MouseButton::Right => match state {
-                        ElementState::Pressed => {
-                            self.grab_position = if gui.using_cursor { None } else { Some(self.absolute_position) };
-                            self.grab_offset = if gui.using_cursor {
-                                None
-                            } else {
-                                Some(renderer.vertex_uniform_buffer_object.offset)
-                            };
-                        }
-                        ElementState::Released => {
-                            self.grab_position = None;
-                            self.grab_offset = None;
+                    ElementState::Released => {
+                        self.picking_color = None;
+                        if gui.action == Action::Erase && matches!(self.state, State::AddPoints) {
+                            renderer.copy_texture(CopyDirection::FrontToBack);
+                            self.state = State::Init;
                         }
-                    },
-                    _ => {}
+                    }
                 }
             }
             WindowEvent::KeyboardInput {
@@ -281,6 +436,24 @@ impl ApplicationHandler for App {
                 event,
                 is_synthetic: false,
             } => match event {
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::Escape),
+                    logical_key: Key::Named(NamedKey::Escape),
+                    text: _,
+                    location: _,
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } if gui.filtering() => gui.cancel_filter(renderer),
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::Enter),
+                    logical_key: Key::Named(NamedKey::Enter),
+                    text: _,
+                    location: _,
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } if gui.filtering() && !gui.using_keyboard => gui.apply_filter(renderer),
                 KeyEvent {
                     physical_key: PhysicalKey::Code(KeyCode::Escape),
                     logical_key: Key::Named(NamedKey::Escape),
@@ -299,6 +472,32 @@ impl ApplicationHandler for App {
                     renderer.window.request_redraw();
                     self.state = State::Init;
                 }
+                KeyEvent {
+                    physical_key: _,
+                    logical_key,
+                    text,
+                    location: _,
+                    state: ElementState::Pressed,
+                    ..
+                } if gui.action == Action::DrawText
+                    && matches!(self.state, State::AddPoints)
+                    && !gui.using_keyboard
+                    && !self.modifiers.control_key()
+                    && (logical_key != Key::Named(NamedKey::Enter) || self.modifiers.shift_key()) =>
+                {
+                    match logical_key {
+                        Key::Named(NamedKey::Backspace) => {
+                            gui.text.pop();
+                        }
+                        Key::Named(NamedKey::Enter) => gui.text.push('\n'),
+                        _ => {
+                            if let Some(text) = text {
+                                gui.text.extend(text.chars().filter(|c| !c.is_control()));
+                            }
+                        }
+                    }
+                    gui.rasterize_text(renderer);
+                }
                 KeyEvent {
                     physical_key: PhysicalKey::Code(KeyCode::Enter),
                     logical_key: Key::Named(NamedKey::Enter),
@@ -312,6 +511,10 @@ impl ApplicationHandler for App {
                     | Action::DrawRectangle
                     | Action::DrawCircle
                     | Action::DrawEllipse
+                    | Action::DrawBezier
+                    | Action::DrawArrow
+                    | Action::DrawRegularShape
+                    | Action::DrawText
                     | Action::CutRectangle => match self.state {
                         State::AddPoints | State::EditPoints => {
                             if !gui.preview {
@@ -326,7 +529,7 @@ impl ApplicationHandler for App {
                         }
                         _ => {}
                     },
-                    Action::DrawPolygon => match self.state {
+                    Action::DrawPolygon | Action::DrawPolyline | Action::DrawSpline => match self.state {
                         State::AddPoints => self.state = State::EditPoints,
                         State::EditPoints => {
                             if !gui.preview {
@@ -343,6 +546,77 @@ impl ApplicationHandler for App {
                     },
                     _ => {}
                 },
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyX),
+                    logical_key: _,
+                    text: _,
+                    location: _,
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } if !gui.using_keyboard => gui.swap_colors(renderer),
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(code @ (KeyCode::KeyZ | KeyCode::KeyY)),
+                    logical_key: _,
+                    text: _,
+                    location: _,
+                    state: ElementState::Pressed,
+                    ..
+                } if !gui.using_keyboard && self.modifiers.control_key() => {
+                    // CTRL+SHIFT+Z redoes as well.
+                    gui.history_step = Some(if code == KeyCode::KeyZ && !self.modifiers.shift_key() {
+                        HistoryStep::Undo
+                    } else {
+                        HistoryStep::Redo
+                    });
+                    renderer.window.request_redraw();
+                }
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(code @ (KeyCode::Digit0 | KeyCode::Digit1 | KeyCode::Digit2)),
+                    logical_key: _,
+                    text: _,
+                    location: _,
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } if !gui.using_keyboard && self.modifiers.control_key() => {
+                    let preset = match code {
+                        KeyCode::Digit0 => ZoomPreset::Fit,
+                        KeyCode::Digit1 => ZoomPreset::ActualPixels,
+                        _ => ZoomPreset::Fill,
+                    };
+                    gui.apply_zoom_preset(renderer, preset);
+                    self.position = renderer.cursor_absolute_to_relative(self.absolute_position);
+                    gui.hover(renderer, Some(self.position));
+                }
+                KeyEvent {
+                    physical_key:
+                        PhysicalKey::Code(
+                            code @ (KeyCode::Digit4
+                            | KeyCode::Digit5
+                            | KeyCode::Digit6
+                            | KeyCode::Numpad4
+                            | KeyCode::Numpad5
+                            | KeyCode::Numpad6
+                            | KeyCode::KeyM),
+                        ),
+                    logical_key: _,
+                    text: _,
+                    location: _,
+                    state: ElementState::Pressed,
+                    ..
+                } if !gui.using_keyboard && !self.modifiers.control_key() => self.transform_view(code),
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::Space),
+                    logical_key: _,
+                    text: _,
+                    location: _,
+                    state,
+                    repeat: false,
+                    ..
+                } if !gui.using_keyboard || state == ElementState::Released => {
+                    self.space_pressed = state == ElementState::Pressed;
+                }
                 KeyEvent {
                     physical_key: PhysicalKey::Code(KeyCode::Delete),
                     logical_key: Key::Named(NamedKey::Delete),
@@ -357,6 +631,9 @@ impl ApplicationHandler for App {
                         | Action::DrawRectangle
                         | Action::DrawCircle
                         | Action::DrawEllipse
+                        | Action::DrawBezier
+                        | Action::DrawArrow
+                        | Action::DrawRegularShape
                         | Action::CutRectangle => {
                             self.grabbed_point_idx = None;
                             renderer.storage_buffer_object.points.clear();
@@ -368,7 +645,7 @@ impl ApplicationHandler for App {
                             renderer.window.request_redraw();
                             self.state = State::Init;
                         }
-                        Action::DrawPolygon => {
+                        Action::DrawPolygon | Action::DrawPolyline | Action::DrawSpline => {
                             if let Some(grabbed_point_idx) = self.grabbed_point_idx {
                                 if grabbed_point_idx == 2 {
                                     self.grabbed_point_idx = None;
@@ -399,6 +676,13 @@ impl ApplicationHandler for App {
                 },
                 _ => {}
             },
+            WindowEvent::CursorLeft {
+                device_id: _,
+            } => {
+                gui.hover(renderer, None);
+                renderer.window.request_redraw();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
             WindowEvent::ScaleFactorChanged {
                 scale_factor,
                 inner_size_writer: _,
@@ -421,19 +705,15 @@ impl ApplicationHandler for App {
                             * 0.01;
                     }
                     gui.zoom = gui.zoom.max(1.0);
-                    renderer.scale_texture(gui.zoom);
-                    renderer.vertex_uniform_buffer_object_changed = true;
+                    renderer.zoom_at(gui.zoom, self.absolute_position);
+                    gui.offset.x = renderer.vertex_uniform_buffer_object.offset.x * 100.0;
+                    gui.offset.y = renderer.vertex_uniform_buffer_object.offset.y * 100.0;
                     self.position = renderer.cursor_absolute_to_relative(self.absolute_position);
-                    if matches!(self.state, State::AddPoints) && !gui.using_cursor {
-                        renderer.storage_buffer_object.points.pop();
-                        renderer.storage_buffer_object.points.push(Vec2::new(self.position.x, self.position.y));
-                        renderer.storage_buffer_object_changed = true;
-                        if gui.preview {
-                            renderer.copy_texture(CopyDirection::BackToFront);
-                            renderer.draw();
-                        }
-                    }
+                    gui.hover(renderer, Some(self.position));
                     renderer.window.request_redraw();
+                    if matches!(self.state, State::AddPoints) {
+                        self.move_last_point();
+                    }
                 }
             }
             WindowEvent::PanGesture {
@@ -444,19 +724,15 @@ impl ApplicationHandler for App {
                 if !gui.using_cursor {
                     gui.zoom += abs_max(delta.x, delta.y) * gui.zoom_speed * 0.01;
                     gui.zoom = gui.zoom.max(1.0);
-                    renderer.scale_texture(gui.zoom);
-                    renderer.vertex_uniform_buffer_object_changed = true;
+                    renderer.zoom_at(gui.zoom, self.absolute_position);
+                    gui.offset.x = renderer.vertex_uniform_buffer_object.offset.x * 100.0;
+                    gui.offset.y = renderer.vertex_uniform_buffer_object.offset.y * 100.0;
                     self.position = renderer.cursor_absolute_to_relative(self.absolute_position);
-                    if matches!(self.state, State::AddPoints) && !gui.using_cursor {
-                        renderer.storage_buffer_object.points.pop();
-                        renderer.storage_buffer_object.points.push(Vec2::new(self.position.x, self.position.y));
-                        renderer.storage_buffer_object_changed = true;
-                        if gui.preview {
-                            renderer.copy_texture(CopyDirection::BackToFront);
-                            renderer.draw();
-                        }
-                    }
+                    gui.hover(renderer, Some(self.position));
                     renderer.window.request_redraw();
+                    if matches!(self.state, State::AddPoints) {
+                        self.move_last_point();
+                    }
                 }
             }
             WindowEvent::PinchGesture {
@@ -470,19 +746,15 @@ impl ApplicationHandler for App {
                         gui.zoom += delta as f32 * gui.zoom_speed * 0.01;
                     }
                     gui.zoom = gui.zoom.max(1.0);
-                    renderer.scale_texture(gui.zoom);
-                    renderer.vertex_uniform_buffer_object_changed = true;
+                    renderer.zoom_at(gui.zoom, self.absolute_position);
+                    gui.offset.x = renderer.vertex_uniform_buffer_object.offset.x * 100.0;
+                    gui.offset.y = renderer.vertex_uniform_buffer_object.offset.y * 100.0;
                     self.position = renderer.cursor_absolute_to_relative(self.absolute_position);
-                    if matches!(self.state, State::AddPoints) && !gui.using_cursor {
-                        renderer.storage_buffer_object.points.pop();
-                        renderer.storage_buffer_object.points.push(Vec2::new(self.position.x, self.position.y));
-                        renderer.storage_buffer_object_changed = true;
-                        if gui.preview {
-                            renderer.copy_texture(CopyDirection::BackToFront);
-                            renderer.draw();
-                        }
-                    }
+                    gui.hover(renderer, Some(self.position));
                     renderer.window.request_redraw();
+                    if matches!(self.state, State::AddPoints) {
+                        self.move_last_point();
+                    }
                 }
             }
             _ => {}
@@ -0,0 +1,98 @@
+use egui::{Color32, Context, Pos2, Rect, Sense, Shape, Slider, Stroke, TextureId, Window};
+
+use crate::{helpers::Position, renderer::Renderer};
+
+// Length of the longer side of the navigator thumbnail in points.
+const NAVIGATOR_SIZE: f32 = 200.0;
+
+/// Thumbnail of the canvas with the part visible in the window outlined.
+pub struct Navigator {
+    pub open: bool,
+    pub texture: TextureId,
+    viewport_grabbed: bool, // Whether the drag started inside the viewport
+}
+
+impl Navigator {
+    pub const fn new(texture: TextureId) -> Self {
+        Self {
+            open: false,
+            texture,
+            viewport_grabbed: false,
+        }
+    }
+
+    /// Moves the view and changes the `zoom` as the navigator is used, returns whether the view was moved.
+    pub fn show(&mut self, context: &Context, renderer: &mut Renderer, zoom: &mut f32) -> bool {
+        let mut moved = false;
+        Window::new("Navigator").open(&mut self.open).resizable(false).show(context, |ui| {
+            renderer.update_thumbnail();
+            #[allow(clippy::cast_precision_loss)]
+            let texture_size = egui::vec2(renderer.texture_size.width as f32, renderer.texture_size.height as f32);
+            let size = texture_size * (NAVIGATOR_SIZE / texture_size.max_elem());
+            let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+            let painter = ui.painter_at(rect);
+            painter.image(self.texture, rect, Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)), Color32::WHITE);
+            // Outline the part of the canvas visible in the window.
+            let to_navigator = |absolute: Position<f32>| {
+                let relative = renderer.cursor_absolute_to_relative(absolute);
+                rect.min + egui::vec2(relative.x, relative.y) / texture_size * size
+            };
+            // All four corners, the view can be rotated.
+            #[allow(clippy::cast_precision_loss)]
+            let (width, height) = (renderer.window_size.width as f32, renderer.window_size.height as f32);
+            let viewport: Vec<Pos2> = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+                .into_iter()
+                .map(|(x, y)| to_navigator(Position::new(x, y)))
+                .collect();
+            if response.drag_started() {
+                self.viewport_grabbed =
+                    ui.input(|input| input.pointer.press_origin()).is_some_and(|origin| contains(&viewport, origin));
+            }
+            // Dragging the viewport moves it, clicking or dragging elsewhere centers the view on the pointer.
+            let center = if self.viewport_grabbed && response.dragged() {
+                let delta = response.drag_delta() / size * texture_size;
+                let center = renderer.cursor_absolute_to_relative(Position::new(width / 2.0, height / 2.0));
+                Some(Position::new(center.x + delta.x, center.y + delta.y))
+            } else if response.dragged() || response.clicked() {
+                // A click inside the viewport doesn't move it.
+                response
+                    .interact_pointer_pos()
+                    .filter(|pointer| response.dragged() || !contains(&viewport, *pointer))
+                    .map(|pointer| {
+                        let point = (pointer - rect.min) / size * texture_size;
+                        Position::new(point.x, point.y)
+                    })
+            } else {
+                None
+            };
+            painter.add(Shape::closed_line(viewport, Stroke::new(2.0, ui.visuals().selection.stroke.color)));
+            if let Some(center) = center {
+                renderer.center_on(center);
+                renderer.window.request_redraw();
+                moved = true;
+            }
+            let mut zoom_changed = false;
+            if response.hovered() {
+                let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+                if scroll != 0.0 {
+                    *zoom = (*zoom * (scroll * 0.01).exp()).max(1.0);
+                    zoom_changed = true;
+                }
+            }
+            zoom_changed |=
+                ui.add(Slider::new(zoom, 1.0..=10000.0).logarithmic(true).suffix("%").text("Zoom")).changed();
+            if zoom_changed {
+                renderer.scale_texture(*zoom);
+                renderer.window.request_redraw();
+            }
+        });
+        moved
+    }
+}
+
+/// Returns whether the convex `polygon` contains `point`, its vertices can go around in either direction.
+fn contains(polygon: &[Pos2], point: Pos2) -> bool {
+    let sides: Vec<f32> =
+        polygon.iter().zip(polygon.iter().cycle().skip(1)).map(|(a, b)| (*b - *a).rot90().dot(point - *a)).collect();
+    sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+}
@@ -0,0 +1,329 @@
+use std::f32::consts::FRAC_PI_2;
+
+use egui::{
+    Area,
+    Color32,
+    Context,
+    CursorIcon,
+    FontId,
+    Id,
+    LayerId,
+    Order,
+    Pos2,
+    Rect,
+    Response,
+    Sense,
+    Stroke,
+    StrokeKind,
+    epaint::TextShape,
+};
+use ultraviolet::Vec2;
+
+use crate::{
+    helpers::{self, Action, Position},
+    renderer::Renderer,
+};
+
+const RULER_SIZE: f32 = 20.0;
+// Minimal distance between two labeled ticks in points.
+const MIN_TICK_SPACING: f32 = 50.0;
+const GUIDE_GRAB_WIDTH: f32 = 6.0;
+const GUIDE_COLOR: Color32 = Color32::from_rgb(0, 170, 255);
+
+struct Guide {
+    vertical: bool,
+    position: f32,
+}
+
+/// Rulers, guides and the measurement readout drawn over the canvas.
+pub struct Overlay {
+    pub rulers: bool,
+    pub snap_to_guides: bool,
+    guides: Vec<Guide>,
+    dragged_guide: Option<usize>,
+}
+
+impl Overlay {
+    pub const fn new() -> Self {
+        Self {
+            rulers: true,
+            snap_to_guides: true,
+            guides: vec![],
+            dragged_guide: None,
+        }
+    }
+
+    pub fn clear_guides(&mut self) {
+        self.guides.clear();
+        self.dragged_guide = None;
+    }
+
+    /// Snaps the coordinates of the point to the guides closer than `tolerance`.
+    pub fn snap(&self, mut point: Vec2, tolerance: f32) -> Vec2 {
+        if !self.snap_to_guides {
+            return point;
+        }
+        for guide in &self.guides {
+            let coordinate = if guide.vertical { &mut point.x } else { &mut point.y };
+            if (*coordinate - guide.position).abs() <= tolerance {
+                *coordinate = guide.position;
+            }
+        }
+        point
+    }
+
+    /// Draws the overlay over the part of the window not covered by panels. The measurement readout of the edited
+    /// shape is drawn next to the cursor when `measuring` is set.
+    pub fn show(&mut self, context: &Context, renderer: &Renderer, action: Action, measuring: bool) {
+        let canvas_rect = context.available_rect();
+        let pixels_per_point = context.pixels_per_point();
+        let to_canvas = |position: Pos2| {
+            let relative = renderer.cursor_absolute_to_relative(Position::new(
+                position.x * pixels_per_point,
+                position.y * pixels_per_point,
+            ));
+            Vec2::new(relative.x, relative.y)
+        };
+        let to_screen = |point: Vec2| {
+            let absolute = renderer.cursor_relative_to_absolute(Position::new(point.x, point.y));
+            Pos2::new(absolute.x / pixels_per_point, absolute.y / pixels_per_point)
+        };
+        let cursor = context.pointer_latest_pos();
+        let mut workspace_rect = canvas_rect;
+        let mut ruler_rects = vec![];
+        // The rulers and guides follow the texture axes, so they are hidden while the view is rotated.
+        let axis_aligned = renderer.view_axis_aligned();
+        if self.rulers && axis_aligned {
+            workspace_rect.min += egui::vec2(RULER_SIZE, RULER_SIZE);
+            ruler_rects.push((
+                Rect::from_min_max(
+                    Pos2::new(workspace_rect.min.x, canvas_rect.min.y),
+                    Pos2::new(canvas_rect.max.x, workspace_rect.min.y),
+                ),
+                false,
+            ));
+            ruler_rects.push((
+                Rect::from_min_max(
+                    Pos2::new(canvas_rect.min.x, workspace_rect.min.y),
+                    Pos2::new(workspace_rect.min.x, canvas_rect.max.y),
+                ),
+                true,
+            ));
+            context.layer_painter(LayerId::new(Order::Background, Id::new("ruler corner"))).rect_filled(
+                Rect::from_min_max(canvas_rect.min, workspace_rect.min),
+                0.0,
+                context.style().visuals.extreme_bg_color,
+            );
+        }
+        // Dragging a ruler creates a new guide perpendicular to it.
+        for &(rect, vertical) in &ruler_rects {
+            let response = ruler(context, rect, vertical, &to_canvas, &to_screen, cursor);
+            if response.drag_started() {
+                self.guides.push(Guide {
+                    vertical,
+                    position: 0.0,
+                });
+                self.dragged_guide = Some(self.guides.len() - 1);
+            }
+        }
+        if let Some(idx) = self.dragged_guide
+            && let Some(cursor) = cursor
+        {
+            let point = to_canvas(cursor);
+            if let Some(guide) = self.guides.get_mut(idx) {
+                guide.position = if guide.vertical { point.x } else { point.y }.round();
+            }
+            if !context.input(|input| input.pointer.primary_down()) {
+                // Dropping the guide back on a ruler or outside of the canvas removes it.
+                if !workspace_rect.contains(cursor) {
+                    self.guides.remove(idx);
+                }
+                self.dragged_guide = None;
+            }
+        }
+        if axis_aligned {
+            self.show_guides(context, workspace_rect, &to_screen);
+        }
+        if measuring
+            && let Some(cursor) = context.pointer_hover_pos()
+            && workspace_rect.contains(cursor)
+            && !context.is_pointer_over_area()
+        {
+            let point = to_canvas(cursor);
+            let mut text = format!("X: {:.0}px, Y: {:.0}px", point.x, point.y);
+            if let Some(measurement) = measurement(action, &renderer.storage_buffer_object.points) {
+                text.push('\n');
+                text.push_str(&measurement);
+            }
+            readout(context, cursor, text);
+        }
+    }
+
+    /// Draws the guides and lets them be dragged around.
+    fn show_guides(&mut self, context: &Context, workspace_rect: Rect, to_screen: &impl Fn(Vec2) -> Pos2) {
+        let guide_painter =
+            context.layer_painter(LayerId::new(Order::Background, Id::new("guides"))).with_clip_rect(workspace_rect);
+        for (idx, guide) in self.guides.iter().enumerate() {
+            let position = to_screen(Vec2::broadcast(guide.position));
+            let rect = if guide.vertical {
+                guide_painter.vline(position.x, workspace_rect.y_range(), Stroke::new(1.0, GUIDE_COLOR));
+                Rect::from_x_y_ranges(
+                    (position.x - GUIDE_GRAB_WIDTH / 2.0)..=(position.x + GUIDE_GRAB_WIDTH / 2.0),
+                    workspace_rect.y_range(),
+                )
+            } else {
+                guide_painter.hline(workspace_rect.x_range(), position.y, Stroke::new(1.0, GUIDE_COLOR));
+                Rect::from_x_y_ranges(
+                    workspace_rect.x_range(),
+                    (position.y - GUIDE_GRAB_WIDTH / 2.0)..=(position.y + GUIDE_GRAB_WIDTH / 2.0),
+                )
+            }
+            .intersect(workspace_rect);
+            if !rect.is_positive() {
+                continue;
+            }
+            let response = Area::new(Id::new(("guide", idx)))
+                .order(Order::Middle)
+                .fixed_pos(rect.min)
+                .show(context, |ui| ui.allocate_exact_size(rect.size(), Sense::drag()).1)
+                .inner
+                .on_hover_cursor(if guide.vertical {
+                    CursorIcon::ResizeHorizontal
+                } else {
+                    CursorIcon::ResizeVertical
+                });
+            if response.drag_started() {
+                self.dragged_guide = Some(idx);
+            }
+        }
+    }
+}
+
+/// Draws the text in a small frame next to the cursor.
+fn readout(context: &Context, cursor: Pos2, text: String) {
+    let visuals = context.style().visuals.clone();
+    let painter = context.layer_painter(LayerId::new(Order::Tooltip, Id::new("measurement")));
+    let galley = painter.layout_no_wrap(text, FontId::monospace(12.0), visuals.text_color());
+    let rect = Rect::from_min_size(cursor + egui::vec2(20.0, 20.0), galley.size()).expand(4.0);
+    painter.rect(rect, 4.0, visuals.window_fill, visuals.window_stroke, StrokeKind::Inside);
+    painter.galley(rect.min + egui::vec2(4.0, 4.0), galley, visuals.text_color());
+}
+
+/// Draws a ruler labeled in canvas pixels, the vertical one shows the y coordinates.
+fn ruler(
+    context: &Context,
+    rect: Rect,
+    vertical: bool,
+    to_canvas: &impl Fn(Pos2) -> Vec2,
+    to_screen: &impl Fn(Vec2) -> Pos2,
+    cursor: Option<Pos2>,
+) -> Response {
+    Area::new(Id::new(("ruler", vertical)))
+        .order(Order::Middle)
+        .fixed_pos(rect.min)
+        .show(context, |ui| {
+            let (rect, response) = ui.allocate_exact_size(rect.size(), Sense::drag());
+            let response = response.on_hover_cursor(if vertical {
+                CursorIcon::ResizeHorizontal
+            } else {
+                CursorIcon::ResizeVertical
+            });
+            let painter = ui.painter_at(rect);
+            let visuals = ui.visuals();
+            let color = visuals.widgets.noninteractive.fg_stroke.color;
+            painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+            let along = |position: Pos2| if vertical { position.y } else { position.x };
+            let axis = |point: Vec2| if vertical { point.y } else { point.x };
+            let (start, end) = (axis(to_canvas(rect.min)), axis(to_canvas(rect.max)));
+            let length = if vertical { rect.height() } else { rect.width() };
+            // Canvas pixels per point.
+            let scale = (end - start).abs() / length;
+            if !scale.is_normal() {
+                return response;
+            }
+            // The labeled ticks are 1, 2 or 5 times a power of ten canvas pixels apart.
+            let min_step = MIN_TICK_SPACING * scale;
+            let magnitude = 10f32.powf(min_step.log10().floor());
+            let step = [1.0, 2.0, 5.0, 10.0]
+                .into_iter()
+                .map(|multiple| multiple * magnitude)
+                .find(|step| *step >= min_step)
+                .unwrap_or(10.0 * magnitude)
+                .max(1.0);
+            let minor_step = (step / 5.0).max(1.0);
+            #[allow(clippy::cast_possible_truncation)]
+            let (first, last) =
+                ((start.min(end) / minor_step).floor() as i64, (start.max(end) / minor_step).ceil() as i64);
+            #[allow(clippy::cast_possible_truncation)]
+            let ticks_per_step = (step / minor_step).round() as i64;
+            for tick in first..=last {
+                #[allow(clippy::cast_precision_loss)]
+                let value = tick as f32 * minor_step;
+                let position = along(to_screen(Vec2::broadcast(value)));
+                let major = tick % ticks_per_step == 0;
+                let tick_length = if major { RULER_SIZE } else { RULER_SIZE / 4.0 };
+                let segment = if vertical {
+                    [Pos2::new(rect.max.x - tick_length, position), Pos2::new(rect.max.x, position)]
+                } else {
+                    [Pos2::new(position, rect.max.y - tick_length), Pos2::new(position, rect.max.y)]
+                };
+                painter.line_segment(segment, Stroke::new(1.0, color));
+                if major {
+                    let galley = painter.layout_no_wrap(format!("{value}"), FontId::monospace(9.0), color);
+                    let text = if vertical {
+                        TextShape::new(Pos2::new(rect.min.x + 1.0, position - 2.0), galley, color)
+                            .with_angle(-FRAC_PI_2)
+                    } else {
+                        TextShape::new(Pos2::new(position + 2.0, rect.min.y + 1.0), galley, color)
+                    };
+                    painter.add(text);
+                }
+            }
+            if let Some(cursor) = cursor {
+                let position = along(cursor);
+                let stroke = Stroke::new(1.0, visuals.selection.bg_fill);
+                if vertical {
+                    painter.hline(rect.x_range(), position, stroke);
+                } else {
+                    painter.vline(position, rect.y_range(), stroke);
+                }
+            }
+            response
+        })
+        .inner
+}
+
+/// Describes the size of the edited shape, lines are described by their length and angle.
+fn measurement(action: Action, points: &[Vec2]) -> Option<String> {
+    let length_and_angle = |from: Vec2, to: Vec2| {
+        let direction = to - from;
+        // The angle is measured counterclockwise with the y axis pointing up.
+        format!("Length: {:.1}px, Angle: {:.1}°", direction.mag(), (-direction.y).atan2(direction.x).to_degrees())
+    };
+    match action {
+        Action::DrawLine | Action::DrawArrow if points.len() >= 2 => Some(length_and_angle(points[0], points[1])),
+        Action::DrawPolygon | Action::DrawPolyline | Action::DrawBezier | Action::DrawSpline if points.len() >= 2 => {
+            Some(length_and_angle(points[points.len() - 2], points[points.len() - 1]))
+        }
+        Action::DrawCircle if points.len() >= 2 => Some(format!("Radius: {:.1}px", (points[1] - points[0]).mag())),
+        Action::DrawRegularShape if points.len() >= 2 => {
+            let direction = points[1] - points[0];
+            Some(format!(
+                "Radius: {:.1}px, Angle: {:.1}°",
+                direction.mag(),
+                (-direction.y).atan2(direction.x).to_degrees()
+            ))
+        }
+        Action::DrawRectangle | Action::DrawEllipse | Action::CutRectangle if points.len() >= 2 => {
+            let rotation = if points.len() >= 3 { helpers::rotation(points) } else { Vec2::unit_x() };
+            let diagonal = points[1] - points[0];
+            let width = rotation.x.mul_add(diagonal.x, rotation.y * diagonal.y).abs();
+            let height = rotation.x.mul_add(diagonal.y, -rotation.y * diagonal.x).abs();
+            Some(format!(
+                "Width: {width:.0}px, Height: {height:.0}px, Angle: {:.1}°",
+                (-rotation.y).atan2(rotation.x).to_degrees()
+            ))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,391 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use egui::Color32;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Equivalent to [`std::io::Error`]
+    #[error("Unable to access the palette file: {0}")]
+    Io(#[from] io::Error),
+    /// Malformed GIMP palette
+    #[error("Invalid GIMP palette on line {0}.")]
+    InvalidGpl(usize),
+    /// Malformed Adobe swatch exchange file
+    #[error("Invalid Adobe swatch exchange file: {0}")]
+    InvalidAse(&'static str),
+    /// Malformed hex color
+    #[error("Invalid hex color on line {0}.")]
+    InvalidHex(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    /// GIMP palette
+    Gpl,
+    /// Adobe swatch exchange
+    Ase,
+    /// One `#RRGGBB` or `#RRGGBBAA` color per line
+    Hex,
+}
+
+impl PaletteFormat {
+    pub const ALL: [Self; 3] = [Self::Gpl, Self::Ase, Self::Hex];
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gpl") => Self::Gpl,
+            Some(extension) if extension.eq_ignore_ascii_case("ase") => Self::Ase,
+            _ => Self::Hex,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Gpl => "GIMP palette (.gpl)",
+            Self::Ase => "Adobe swatch exchange (.ase)",
+            Self::Hex => "Hex list (.hex)",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<Color32>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_owned(),
+            colors: vec![
+                Color32::BLACK,
+                Color32::WHITE,
+                Color32::RED,
+                Color32::GREEN,
+                Color32::BLUE,
+                Color32::YELLOW,
+                Color32::PURPLE,
+            ],
+        }
+    }
+}
+
+impl Palette {
+    pub fn load(path: &Path, format: PaletteFormat) -> Result<Self, Error> {
+        let mut palette = match format {
+            PaletteFormat::Gpl => Self::from_gpl(&fs::read_to_string(path)?)?,
+            PaletteFormat::Ase => Self::from_ase(&fs::read(path)?)?,
+            PaletteFormat::Hex => Self::from_hex(&fs::read_to_string(path)?)?,
+        };
+        if palette.name.is_empty() {
+            palette.name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        }
+        Ok(palette)
+    }
+
+    pub fn save(&self, path: &Path, format: PaletteFormat) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match format {
+            PaletteFormat::Gpl => fs::write(path, self.to_gpl())?,
+            PaletteFormat::Ase => fs::write(path, self.to_ase())?,
+            PaletteFormat::Hex => fs::write(path, self.to_hex())?,
+        }
+        Ok(())
+    }
+
+    /// Moves `color` to the front, keeping at most `capacity` colors.
+    pub fn push_recent(&mut self, color: Color32, capacity: usize) {
+        self.colors.retain(|recent| *recent != color);
+        self.colors.insert(0, color);
+        self.colors.truncate(capacity);
+    }
+
+    // https://developer.gimp.org/core/standards/gpl/
+    fn from_gpl(text: &str) -> Result<Self, Error> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().is_none_or(|(_, line)| line.trim() != "GIMP Palette") {
+            return Err(Error::InvalidGpl(1));
+        }
+        let mut palette = Self {
+            name: String::new(),
+            colors: vec![],
+        };
+        for (idx, line) in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("Name:") {
+                name.trim().clone_into(&mut palette.name);
+                continue;
+            }
+            let mut channels = line.split_whitespace().map(str::parse::<u8>);
+            match (channels.next(), channels.next(), channels.next()) {
+                (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => palette.colors.push(Color32::from_rgb(r, g, b)),
+                _ => return Err(Error::InvalidGpl(idx + 1)),
+            }
+        }
+        Ok(palette)
+    }
+
+    fn to_gpl(&self) -> String {
+        let mut text = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", self.name);
+        for color in &self.colors {
+            let [r, g, b, _] = color.to_srgba_unmultiplied();
+            // Writing into a `String` never fails.
+            writeln!(text, "{r:3} {g:3} {b:3}\t#{r:02x}{g:02x}{b:02x}").ok();
+        }
+        text
+    }
+
+    // http://www.selapa.net/swatches/colors/fileformats.php#adobe_ase
+    fn from_ase(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = AseReader {
+            bytes,
+        };
+        if reader.take(4)? != b"ASEF" {
+            return Err(Error::InvalidAse("missing `ASEF` signature"));
+        }
+        reader.take(4)?; // Version
+        let block_count = reader.u32()?;
+        let mut palette = Self {
+            name: String::new(),
+            colors: vec![],
+        };
+        for _ in 0..block_count {
+            let block_type = reader.u16()?;
+            let block_length = reader.u32()? as usize;
+            let mut block = AseReader {
+                bytes: reader.take(block_length)?,
+            };
+            match block_type {
+                // Group start
+                0xc001 if palette.name.is_empty() => palette.name = block.string()?,
+                // Color entry
+                0x0001 => {
+                    block.string()?;
+                    let model = block.take(4)?;
+                    let color = match model {
+                        b"RGB " => [block.f32()?, block.f32()?, block.f32()?],
+                        b"Gray" => [block.f32()?; 3],
+                        b"CMYK" => {
+                            let [c, m, y, k] = [block.f32()?, block.f32()?, block.f32()?, block.f32()?];
+                            [(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)]
+                        }
+                        b"LAB " => lab_to_srgb(block.f32()? * 100.0, block.f32()?, block.f32()?),
+                        _ => return Err(Error::InvalidAse("unknown color model")),
+                    };
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let [r, g, b] = color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+                    palette.colors.push(Color32::from_rgb(r, g, b));
+                }
+                _ => {}
+            }
+        }
+        Ok(palette)
+    }
+
+    fn to_ase(&self) -> Vec<u8> {
+        fn push_string(bytes: &mut Vec<u8>, string: &str) {
+            let units: Vec<u16> = string.encode_utf16().chain([0]).collect();
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.extend_from_slice(&(units.len() as u16).to_be_bytes());
+            bytes.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+        }
+
+        fn push_block(bytes: &mut Vec<u8>, block_type: u16, block: &[u8]) {
+            bytes.extend_from_slice(&block_type.to_be_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.extend_from_slice(&(block.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(block);
+        }
+
+        let mut bytes = b"ASEF".to_vec();
+        bytes.extend_from_slice(&1_u16.to_be_bytes());
+        bytes.extend_from_slice(&0_u16.to_be_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.extend_from_slice(&(self.colors.len() as u32 + 2).to_be_bytes());
+        let mut block = vec![];
+        push_string(&mut block, &self.name);
+        push_block(&mut bytes, 0xc001, &block);
+        for color in &self.colors {
+            let [r, g, b, _] = color.to_srgba_unmultiplied();
+            block.clear();
+            push_string(&mut block, &format!("#{r:02x}{g:02x}{b:02x}"));
+            block.extend_from_slice(b"RGB ");
+            for channel in [r, g, b] {
+                block.extend_from_slice(&(f32::from(channel) / 255.0).to_be_bytes());
+            }
+            block.extend_from_slice(&2_u16.to_be_bytes()); // Normal color type
+            push_block(&mut bytes, 0x0001, &block);
+        }
+        push_block(&mut bytes, 0xc002, &[]);
+        bytes
+    }
+
+    fn from_hex(text: &str) -> Result<Self, Error> {
+        let mut palette = Self {
+            name: String::new(),
+            colors: vec![],
+        };
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with("//") {
+                continue;
+            }
+            let hex = line.strip_prefix('#').unwrap_or(line);
+            if hex.len() != 6 && hex.len() != 8 {
+                return Err(Error::InvalidHex(idx + 1));
+            }
+            palette.colors.push(Color32::from_hex(&format!("#{hex}")).map_err(|_| Error::InvalidHex(idx + 1))?);
+        }
+        Ok(palette)
+    }
+
+    fn to_hex(&self) -> String {
+        self.colors.iter().fold(String::new(), |mut text, color| {
+            let [r, g, b, a] = color.to_srgba_unmultiplied();
+            // Writing into a `String` never fails.
+            if a == u8::MAX {
+                writeln!(text, "#{r:02x}{g:02x}{b:02x}").ok();
+            } else {
+                writeln!(text, "#{r:02x}{g:02x}{b:02x}{a:02x}").ok();
+            }
+            text
+        })
+    }
+}
+
+struct AseReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> AseReader<'a> {
+    const fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < length {
+            return Err(Error::InvalidAse("unexpected end of file"));
+        }
+        let (head, tail) = self.bytes.split_at(length);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32, Error> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self.u16()?;
+        let units = (0..length).map(|_| self.u16()).collect::<Result<Vec<_>, _>>()?;
+        Ok(String::from_utf16_lossy(&units).trim_end_matches('\0').to_owned())
+    }
+}
+
+// https://en.wikipedia.org/wiki/CIELAB_color_space#From_CIELAB_to_CIEXYZ
+// https://en.wikipedia.org/wiki/SRGB#From_CIE_XYZ_to_sRGB
+fn lab_to_srgb(lightness: f32, green_red: f32, blue_yellow: f32) -> [f32; 3] {
+    let inverse = |t: f32| {
+        if t > 6.0 / 29.0 { t.powi(3) } else { 3.0 * (6.0_f32 / 29.0).powi(2) * (t - 4.0 / 29.0) }
+    };
+    let fy = (lightness + 16.0) / 116.0;
+    // D50 white point
+    let xyz = [0.964_22 * inverse(fy + green_red / 500.0), inverse(fy), 0.825_21 * inverse(fy - blue_yellow / 200.0)];
+    // Bradford-adapted D50 XYZ to linear sRGB
+    [[3.133_856, -1.616_867, -0.490_615], [-0.978_768, 1.916_141, 0.033_454], [0.071_945, -0.228_991, 1.405_243]].map(
+        |row: [f32; 3]| {
+            let linear = row[2].mul_add(xyz[2], row[1].mul_add(xyz[1], row[0] * xyz[0]));
+            if linear <= 0.003_130_8 { 12.92 * linear } else { 1.055_f32.mul_add(linear.powf(1.0 / 2.4), -0.055) }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> Palette {
+        Palette {
+            name: "Test".to_owned(),
+            colors: vec![Color32::BLACK, Color32::from_rgb(18, 52, 86), Color32::WHITE],
+        }
+    }
+
+    #[test]
+    fn gpl_round_trip() {
+        let palette = palette();
+        let loaded = Palette::from_gpl(&palette.to_gpl()).expect("valid GIMP palette");
+        assert_eq!(loaded.name, palette.name);
+        assert_eq!(loaded.colors, palette.colors);
+    }
+
+    #[test]
+    fn ase_round_trip() {
+        let palette = palette();
+        let loaded = Palette::from_ase(&palette.to_ase()).expect("valid Adobe swatch exchange file");
+        assert_eq!(loaded.name, palette.name);
+        assert_eq!(loaded.colors, palette.colors);
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let mut palette = palette();
+        palette.colors.push(Color32::from_rgba_unmultiplied(255, 0, 0, 128));
+        let loaded = Palette::from_hex(&palette.to_hex()).expect("valid hex list");
+        assert_eq!(loaded.colors, palette.colors);
+    }
+
+    #[test]
+    fn gpl_comments_and_names() {
+        let loaded = Palette::from_gpl("GIMP Palette\nName: Named\nColumns: 4\n# Comment\n\n  1 2 3\tFirst\n")
+            .expect("valid GIMP palette");
+        assert_eq!(loaded.name, "Named");
+        assert_eq!(loaded.colors, [Color32::from_rgb(1, 2, 3)]);
+    }
+
+    #[test]
+    fn malformed_gpl() {
+        assert!(matches!(Palette::from_gpl(""), Err(Error::InvalidGpl(1))));
+        assert!(matches!(Palette::from_gpl("Palette\n0 0 0\n"), Err(Error::InvalidGpl(1))));
+        assert!(matches!(Palette::from_gpl("GIMP Palette\n0 0 0\n0 256 0\n"), Err(Error::InvalidGpl(3))));
+        assert!(matches!(Palette::from_gpl("GIMP Palette\n0 0\n"), Err(Error::InvalidGpl(2))));
+    }
+
+    #[test]
+    fn malformed_ase() {
+        assert!(matches!(Palette::from_ase(b"ASEX\0\x01\0\0\0\0\0\0"), Err(Error::InvalidAse(_))));
+        let bytes = palette().to_ase();
+        for length in [0, 4, 12, bytes.len() - 1] {
+            assert!(matches!(Palette::from_ase(&bytes[..length]), Err(Error::InvalidAse(_))), "length {length}");
+        }
+        let mut bytes = bytes;
+        // Color model of the first color entry.
+        let model = bytes.windows(4).position(|window| window == b"RGB ").expect("color model");
+        bytes[model..model + 4].copy_from_slice(b"XYZ ");
+        assert!(matches!(Palette::from_ase(&bytes), Err(Error::InvalidAse("unknown color model"))));
+    }
+
+    #[test]
+    fn malformed_hex() {
+        assert!(matches!(Palette::from_hex("#000000\n#12345\n"), Err(Error::InvalidHex(2))));
+        assert!(matches!(Palette::from_hex("; Comment\n#gggggg\n"), Err(Error::InvalidHex(2))));
+    }
+}
@@ -1,16 +1,19 @@
 // TODO: Clean-up, visibility.
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    array,
+    collections::{HashMap, VecDeque},
+    sync::{Arc, mpsc},
+};
 
 use bytemuck::{Pod, Zeroable};
-use smallvec::{SmallVec, smallvec};
 use thiserror::Error;
-use ultraviolet::{Vec2, Vec4};
+use ultraviolet::{IVec2, Mat2, UVec2, Vec2, Vec4};
 use wgpu::{
-    AddressMode,
     Backends,
     BindGroup,
     BindGroupDescriptor,
     BindGroupEntry,
+    BindGroupLayout,
     BindGroupLayoutDescriptor,
     BindGroupLayoutEntry,
     BindingResource,
@@ -18,6 +21,7 @@ use wgpu::{
     BlendState,
     Buffer,
     BufferAddress,
+    BufferAsyncError,
     BufferBindingType,
     BufferDescriptor,
     BufferSize,
@@ -34,9 +38,10 @@ use wgpu::{
     CreateSurfaceError,
     Device,
     DeviceDescriptor,
+    DeviceType,
+    ErrorFilter,
     Extent3d,
     Features,
-    FilterMode,
     FragmentState,
     Instance,
     InstanceDescriptor,
@@ -45,6 +50,7 @@ use wgpu::{
     MapMode,
     MultisampleState,
     Operations,
+    Origin3d,
     PipelineCompilationOptions,
     PipelineLayoutDescriptor,
     PowerPreference,
@@ -57,8 +63,9 @@ use wgpu::{
     RenderPipelineDescriptor,
     RequestAdapterOptions,
     RequestDeviceError,
-    SamplerBindingType,
-    SamplerDescriptor,
+    ShaderModule,
+    ShaderModuleDescriptor,
+    ShaderSource,
     ShaderStages,
     StorageTextureAccess,
     StoreOp,
@@ -67,10 +74,13 @@ use wgpu::{
     SurfaceError,
     TexelCopyBufferInfo,
     TexelCopyBufferLayout,
+    TexelCopyTextureInfo,
     Texture,
+    TextureAspect,
     TextureDescriptor,
     TextureDimension,
     TextureFormat,
+    TextureFormatFeatureFlags,
     TextureSampleType,
     TextureUsages,
     TextureView,
@@ -85,7 +95,24 @@ use wgpu::{
 };
 use winit::window::Window;
 
-use crate::helpers::{Position, Size};
+use crate::{
+    color::{self, CanvasFormat},
+    filter::{self, Adjustment, AdjustmentParameters, Convolution, ConvolutionParameters},
+    helpers::{self, Action, AntiAliasing, ImageTransform, Position, Size, ZoomPreset},
+    tiling::Tiling,
+};
+
+// Same as in compute.wgsl.
+const MITER_LIMIT: f32 = 4.0;
+// Length of the longer side of the navigator thumbnail in pixels.
+const THUMBNAIL_SIZE: u32 = 512;
+// Number of points the storage buffer has room for before it has to grow.
+const INITIAL_POINTS: usize = 4096;
+// Memory the undo history may take up in bytes, the oldest snapshots are dropped first. Integrated GPUs share the
+// system memory with everything else, so their history is four times smaller, still enough for two snapshots of a
+// whole 4K canvas in 16-bit floats.
+const HISTORY_SIZE: u64 = 1 << 29;
+const INTEGRATED_HISTORY_SIZE: u64 = HISTORY_SIZE / 4;
 
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -102,48 +129,49 @@ pub enum Error {
     /// Equivalent to [`wgpu::SurfaceError`]
     #[error("The GPU failed to acquire a surface frame.")]
     Surface(#[from] SurfaceError),
-    /// No texture format found
-    #[error("No `wgpu::TextureFormat` found.")]
+    /// No surface texture format without sRGB encoding can be rendered to
+    #[error("No `wgpu::TextureFormat` of the surface without sRGB encoding supports rendering.")]
     TextureFormatNotFound,
     /// No present mode found
     #[error("No `wgpu::PresentMode` found.")]
     PresentModeNotFound,
-    /// Unable to create a backing texture; Width is greater than GPU limits
+    /// Unable to create a backing texture; Width is zero
     #[error("Texture width is invalid: {0}")]
     TextureWidth(u32),
-    /// Unable to create a backing texture; Height is greater than GPU limits
+    /// Unable to create a backing texture; Height is zero
     #[error("Texture height is invalid: {0}")]
     TextureHeight(u32),
+    /// Unable to create a backing texture; The canvas needs more tiles than the GPU supports array layers
+    #[error("The canvas needs {0} tiles, but at most {1} are supported.")]
+    TileCount(u32, u32),
 }
 
 // #[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[derive(Default)]
 #[repr(C)]
 pub struct StorageBufferObject {
     pub length: u32,
     padding: [u8; 4],
-    pub points: SmallVec<[Vec2; 4096]>,
+    pub points: Vec<Vec2>,
 }
 
 impl StorageBufferObject {
-    pub fn as_bytes(&self) -> SmallVec<[u8; size_of::<Self>()]> {
-        let mut vec = SmallVec::new();
-        vec.extend_from_slice(bytemuck::bytes_of(&self.length)); // TODO: self.points.len()
+    // Size of the length and the padding in front of the points.
+    const HEADER_SIZE: usize = 8;
+
+    /// Returns the bytes of the first `length` points, but at most `max_points` of them. The length is adjusted to
+    /// the number of points.
+    pub fn as_bytes(&self, max_points: usize) -> Vec<u8> {
+        let length = (self.length as usize).min(self.points.len()).min(max_points);
+        let mut vec = Vec::with_capacity(Self::HEADER_SIZE + length * size_of::<Vec2>());
+        #[allow(clippy::cast_possible_truncation)]
+        vec.extend_from_slice(bytemuck::bytes_of(&(length as u32)));
         vec.extend_from_slice(bytemuck::cast_slice(&self.padding));
-        vec.extend_from_slice(bytemuck::cast_slice(&self.points));
+        vec.extend_from_slice(bytemuck::cast_slice(&self.points[..length]));
         vec
     }
 }
 
-impl Default for StorageBufferObject {
-    fn default() -> Self {
-        Self {
-            length: 0,
-            padding: Default::default(),
-            points: smallvec![Vec2::default(); 4096],
-        }
-    }
-}
-
 // Respect std140 alignment!
 #[derive(Clone, Copy, Default, Pod, Zeroable, Debug)]
 #[repr(C)]
@@ -154,8 +182,23 @@ pub struct ComputeUniformBufferObject {
     pub anti_aliasing_scale: f32,
     pub dash_length: f32,
     pub gap_length: f32,
-    _padding: [u8; 12],
+    pub line_join: u32,
+    pub line_cap: u32,
+    pub arrow_start: u32,
+    pub arrow_end: u32,
+    pub arrow_size: f32,
+    pub corner_radius: f32,
+    pub sides: u32,
+    pub star_ratio: f32,
+    pub anti_aliasing: u32, // 0 is aliased, otherwise `AntiAliasing`
+    // Set by `Renderer::draw` to the origin of the dispatched region.
+    origin: UVec2,
+    canvas_size: UVec2,
+    pub samples: u32, // Per axis of a pixel for supersampling
+    _padding: [u8; 4],
 }
+// Same size as the uniform buffer object in compute.wgsl.
+const _: () = assert!(size_of::<ComputeUniformBufferObject>() == 96);
 
 // Respect std140 alignment!
 #[derive(Clone, Copy, Default, Pod, Zeroable)]
@@ -163,6 +206,11 @@ pub struct ComputeUniformBufferObject {
 pub struct VertexUniformBufferObject {
     pub scale: Vec2,
     pub offset: Vec2,
+    pub transform: Mat2,
+    pub window_size: Vec2,
+    canvas_size: Vec2,
+    tile_size: Vec2,
+    _padding: [u8; 8],
 }
 
 // Respect std140 alignment!
@@ -172,14 +220,107 @@ pub struct FragmentUniformBufferObject {
     pub grid_scale: Vec2,
     pub action: u32,
     pub preview: u32, // bool
+    pub corner_radius: f32,
+    pub sides: u32,
+    pub star_ratio: f32,
+    pub grid_subdivisions: u32,
+    pub grid_color: Vec4,
+    pub pixel_grid: u32,   // bool
+    linear_filtering: u32, // bool
+    _padding: [u8; 8],
+}
+
+// Respect std140 alignment!
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[repr(C)]
+struct TransformUniformBufferObject {
+    origin: IVec2,
+    x_axis: IVec2,
+    y_axis: IVec2,
+    source_size: UVec2,
+    canvas_size: UVec2,
+}
+
+// Respect std140 alignment!
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct AdjustmentUniformBufferObject {
+    region_origin: UVec2,
+    region_size: UVec2,
+    canvas_size: UVec2,
+    adjustment: u32,
+    brightness: f32,
+    contrast: f32,
+    hue: f32,
+    saturation: f32,
+    lightness: f32,
+    input_black: f32,
+    input_white: f32,
+    gamma: f32,
+    output_black: f32,
+    output_white: f32,
+    posterize_levels: u32,
+    threshold: f32,
+    _padding: [u8; 4],
+    curve: [Vec4; 64],
+}
+
+// Respect std140 alignment!
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct ConvolutionUniformBufferObject {
+    region_origin: UVec2,
+    region_size: UVec2,
+    canvas_size: UVec2,
+    convolution: u32,
+    radius: u32,
+    direction: IVec2,
+    sigma: f32,
+    amount: f32,
+    threshold: f32,
+    kernel_size: u32,
+    divisor: f32,
+    bias: f32,
+    last_pass: u32,
+    _padding: [u8; 12],
+    kernel: [Vec4; 7],
+}
+
+// Respect std140 alignment!
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+#[repr(C)]
+struct ThumbnailUniformBufferObject {
+    canvas_size: UVec2,
+    _padding: [u8; 8],
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CopyDirection {
     BackToFront,
     FrontToBack,
 }
 
+/// Committed state of the canvas that an undo or a redo restores.
+enum Snapshot {
+    /// The region of the canvas, the parts in each tile are stored in consecutive layers.
+    Region { origin: Origin3d, extent: Extent3d, texture: Texture },
+    /// The whole back texture of a canvas of different size.
+    Canvas { size: Size<u32>, texture: Texture },
+}
+
+impl Snapshot {
+    const fn texture(&self) -> &Texture {
+        match self {
+            Self::Region {
+                texture, ..
+            }
+            | Self::Canvas {
+                texture, ..
+            } => texture,
+        }
+    }
+}
+
 pub struct Renderer {
     pub window: Arc<Window>,
     pub window_size: Size<u32>,
@@ -188,16 +329,32 @@ pub struct Renderer {
     surface: Surface<'static>,
     pub texture_size: Size<u32>,
     texture_extent: Extent3d,
+    tiling: Tiling,
     pub texture_format: TextureFormat,
+    pub canvas_format: CanvasFormat,
     present_mode: PresentMode,
     back_texture: Texture,
     front_texture: Texture,
+    filter_texture: Texture,
+    text_mask_texture: Texture,
+    thumbnail_texture: Texture,
+    text_mask_region: Option<(Origin3d, Extent3d)>,
+    // The front texture differs from the back texture only inside this region.
+    dirty_region: Option<(Origin3d, Extent3d)>,
+    // The mipmaps of the front texture are outdated only inside this region.
+    mipmap_dirty_region: Option<(Origin3d, Extent3d)>,
+    // The front texture changed since the navigator thumbnail was last downsampled.
+    thumbnail_outdated: bool,
     pub storage_buffer_object: StorageBufferObject,
     pub storage_buffer_object_changed: bool,
     storage_buffer: Buffer,
+    // Limited by the maximum storage buffer binding size, the rest of the points isn't uploaded.
+    pub max_points: usize,
+    points_truncated: bool,
     pub compute_uniform_buffer_object: ComputeUniformBufferObject,
     pub compute_uniform_buffer_object_changed: bool,
     compute_uniform_buffer: Buffer,
+    compute_bind_group_layout: BindGroupLayout,
     compute_bind_group: BindGroup,
     compute_pipeline: ComputePipeline,
     pub vertex_uniform_buffer_object: VertexUniformBufferObject,
@@ -206,15 +363,44 @@ pub struct Renderer {
     pub fragment_uniform_buffer_object: FragmentUniformBufferObject,
     pub fragment_uniform_buffer_object_changed: bool,
     fragment_uniform_buffer: Buffer,
+    render_bind_group_layout: BindGroupLayout,
     render_bind_group: BindGroup,
+    pub linear_filtering: bool,
+    mipmap_bind_group_layout: BindGroupLayout,
+    mipmap_pipeline: ComputePipeline,
+    mipmap_bind_groups: Vec<BindGroup>,
+    // Layers of the tiles whose mipmaps are regenerated, one per workgroup layer.
+    mipmap_layer_buffer: Buffer,
+    // Shared by all the image passes, each of them is submitted right after writing its uniform buffer object.
+    image_uniform_buffer: Buffer,
+    image_bind_group_layout: BindGroupLayout,
+    thumbnail_bind_group_layout: BindGroupLayout,
+    // The bind groups of the image passes are named after their source and destination texture.
+    back_to_front_bind_group: BindGroup,
+    back_to_filter_bind_group: BindGroup,
+    filter_to_front_bind_group: BindGroup,
+    thumbnail_bind_group: BindGroup,
+    transform_pipeline: ComputePipeline,
+    adjustment_pipeline: ComputePipeline,
+    convolution_pipeline: ComputePipeline,
+    thumbnail_pipeline: ComputePipeline,
     vertex_buffer: Buffer,
+    // Layers of the visible tiles, one per instance.
+    tile_buffer: Buffer,
     render_pipeline: RenderPipeline,
     fill_buffer_bytes_per_row: u32,
     fill_buffer: Buffer,
+    // Holds the pixel under the cursor, which is read back without blocking.
+    hover_buffer: Buffer,
+    hover_readback: Option<mpsc::Receiver<Result<(), BufferAsyncError>>>,
+    undo_history: VecDeque<Snapshot>,
+    redo_history: Vec<Snapshot>,
+    history_size: u64,
 }
 
 impl Renderer {
-    pub async fn new(window: Arc<Window>, texture_size: Size<u32>) -> Result<Self, Error> {
+    /// Falls back to [`CanvasFormat::Rgba8`] when `canvas_format` can't be used for read-write storage textures.
+    pub async fn new(window: Arc<Window>, texture_size: Size<u32>, canvas_format: CanvasFormat) -> Result<Self, Error> {
         let instance = Instance::new(&InstanceDescriptor {
             backends: Backends::PRIMARY,
             ..Default::default()
@@ -240,12 +426,26 @@ impl Renderer {
             )
             .await?;
         let limits = device.limits();
-        if texture_size.width == 0 || texture_size.width > limits.max_texture_dimension_2d {
+        let canvas_format = if adapter
+            .get_texture_format_features(canvas_format.texture_format())
+            .flags
+            .contains(TextureFormatFeatureFlags::STORAGE_READ_WRITE)
+        {
+            canvas_format
+        } else {
+            log::warn!("{:?} can't be used for the canvas, falling back to 8 bits", canvas_format.texture_format());
+            CanvasFormat::Rgba8
+        };
+        if texture_size.width == 0 {
             return Err(Error::TextureWidth(texture_size.width));
         }
-        if texture_size.height == 0 || texture_size.height > limits.max_texture_dimension_2d {
+        if texture_size.height == 0 {
             return Err(Error::TextureHeight(texture_size.height));
         }
+        let tiling = Tiling::new(texture_size, limits.max_texture_dimension_2d);
+        if tiling.layers() > limits.max_texture_array_layers {
+            return Err(Error::TileCount(tiling.layers(), limits.max_texture_array_layers));
+        }
         let window_size = Size::<u32>::from(window.inner_size());
         let surface = instance.create_surface(window.clone())?;
         let capabilities = surface.get_capabilities(&adapter);
@@ -257,18 +457,13 @@ impl Renderer {
         let texture_format = capabilities
             .formats
             .into_iter()
+            // The render pass encodes the colors into sRGB itself.
             .filter(|format| {
-                let features = format.guaranteed_format_features(device.features());
-                features.allowed_usages.contains(
-                    TextureUsages::COPY_SRC
-                        | TextureUsages::COPY_DST
-                        | TextureUsages::TEXTURE_BINDING
-                        | TextureUsages::STORAGE_BINDING
-                        | TextureUsages::RENDER_ATTACHMENT,
-                )
-                // && features
-                // .flags
-                // .contains(TextureFormatFeatureFlags::STORAGE_READ_WRITE)
+                !format.is_srgb()
+                    && adapter
+                        .get_texture_format_features(*format)
+                        .allowed_usages
+                        .contains(TextureUsages::RENDER_ATTACHMENT)
             })
             .max_by_key(|format| match format {
                 TextureFormat::Rgba8Unorm => 2,
@@ -287,41 +482,25 @@ impl Renderer {
                 _ => 0,
             })
             .ok_or(Error::PresentModeNotFound)?;
-        let back_texture = device.create_texture(&TextureDescriptor {
-            label: Some("back texture"),
-            size: texture_extent,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: texture_format,
-            usage: TextureUsages::COPY_SRC | TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
-        let front_texture = device.create_texture(&TextureDescriptor {
-            label: Some("front texture"),
-            size: texture_extent,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: texture_format,
-            usage: TextureUsages::COPY_SRC
-                | TextureUsages::COPY_DST
-                | TextureUsages::TEXTURE_BINDING
-                | TextureUsages::STORAGE_BINDING,
-            view_formats: &[],
-        });
-        let front_texture_view = front_texture.create_view(&TextureViewDescriptor {
-            label: Some("front texture view"),
-            ..Default::default()
-        });
+        let (back_texture, front_texture, text_mask_texture, filter_texture) =
+            Self::create_textures(&device, canvas_format.texture_format(), tiling.extent());
+        let thumbnail_texture = Self::create_thumbnail_texture(&device, texture_size);
         let storage_buffer_object = StorageBufferObject::default();
-        let storage_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("storage buffer"),
-            contents: &storage_buffer_object.as_bytes(),
-            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-        });
-        let compute_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/compute.wgsl"));
-        let compute_uniform_buffer_object = ComputeUniformBufferObject::default();
+        let storage_buffer = Self::create_storage_buffer(&device, INITIAL_POINTS);
+        #[allow(clippy::cast_possible_truncation)]
+        let max_points = (u64::from(limits.max_storage_buffer_binding_size).min(limits.max_buffer_size) as usize
+            - StorageBufferObject::HEADER_SIZE)
+            / size_of::<Vec2>();
+        let compute_shader = Self::create_shader_module(
+            &device,
+            canvas_format,
+            "compute shader",
+            concat!(include_str!("shaders/shapes.wgsl"), "\n", include_str!("shaders/compute.wgsl")),
+        );
+        let compute_uniform_buffer_object = ComputeUniformBufferObject {
+            canvas_size: UVec2::new(texture_size.width, texture_size.height),
+            ..Default::default()
+        };
         let compute_uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("compute uniform buffer"),
             contents: bytemuck::cast_slice(&[compute_uniform_buffer_object]),
@@ -357,31 +536,33 @@ impl Renderer {
                     visibility: ShaderStages::COMPUTE,
                     ty: BindingType::StorageTexture {
                         access: StorageTextureAccess::ReadWrite,
-                        format: texture_format,
-                        view_dimension: TextureViewDimension::D2,
+                        format: canvas_format.texture_format(),
+                        view_dimension: TextureViewDimension::D2Array,
                     },
                     count: None,
                 },
-            ],
-        });
-        let compute_bind_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("compute bind group"),
-            layout: &compute_bind_group_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: storage_buffer.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: compute_uniform_buffer.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 2,
-                    resource: BindingResource::TextureView(&front_texture_view),
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: false,
+                        },
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
                 },
             ],
         });
+        let compute_bind_group = Self::create_compute_bind_group(
+            &device,
+            &compute_bind_group_layout,
+            &storage_buffer,
+            &compute_uniform_buffer,
+            &front_texture,
+            &text_mask_texture,
+        );
         let compute_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("compute pipeline layout"),
             bind_group_layouts: &[&compute_bind_group_layout],
@@ -402,8 +583,186 @@ impl Renderer {
             compilation_options: PipelineCompilationOptions::default(),
             cache: None, // Some(&compute_pipeline_cache)
         });
-        let render_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/render.wgsl"));
-        let vertex_uniform_buffer_object = VertexUniformBufferObject::default();
+        let mipmap_shader =
+            Self::create_shader_module(&device, canvas_format, "mipmap shader", include_str!("shaders/mipmap.wgsl"));
+        let mipmap_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("mipmap bind group layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: false,
+                        },
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: canvas_format.texture_format(),
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage {
+                            read_only: true,
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let mipmap_layer_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("mipmap layer buffer"),
+            size: u64::from(limits.max_texture_array_layers) * size_of::<u32>() as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let mipmap_bind_groups =
+            Self::create_mipmap_bind_groups(&device, &mipmap_bind_group_layout, &front_texture, &mipmap_layer_buffer);
+        let mipmap_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("mipmap pipeline layout"),
+            bind_group_layouts: &[&mipmap_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let mipmap_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("mipmap pipeline"),
+            layout: Some(&mipmap_pipeline_layout),
+            module: &mipmap_shader,
+            entry_point: Some("downsample"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let image_bind_group_layout =
+            Self::create_image_bind_group_layout(&device, "image bind group layout", canvas_format.texture_format());
+        let image_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("image pipeline layout"),
+            bind_group_layouts: &[&image_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let transform_shader = Self::create_shader_module(
+            &device,
+            canvas_format,
+            "transform shader",
+            include_str!("shaders/transform.wgsl"),
+        );
+        let transform_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("transform pipeline"),
+            layout: Some(&image_pipeline_layout),
+            module: &transform_shader,
+            entry_point: Some("transform"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let adjustment_shader = Self::create_shader_module(
+            &device,
+            canvas_format,
+            "adjustment shader",
+            include_str!("shaders/adjustment.wgsl"),
+        );
+        let adjustment_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("adjustment pipeline"),
+            layout: Some(&image_pipeline_layout),
+            module: &adjustment_shader,
+            entry_point: Some("adjust"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let convolution_shader = Self::create_shader_module(
+            &device,
+            canvas_format,
+            "convolution shader",
+            include_str!("shaders/convolution.wgsl"),
+        );
+        let convolution_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("convolution pipeline"),
+            layout: Some(&image_pipeline_layout),
+            module: &convolution_shader,
+            entry_point: Some("convolve"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let thumbnail_bind_group_layout =
+            Self::create_image_bind_group_layout(&device, "thumbnail bind group layout", TextureFormat::Rgba8Unorm);
+        let thumbnail_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("thumbnail pipeline layout"),
+            bind_group_layouts: &[&thumbnail_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let thumbnail_shader = Self::create_shader_module(
+            &device,
+            canvas_format,
+            "thumbnail shader",
+            include_str!("shaders/thumbnail.wgsl"),
+        );
+        let thumbnail_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("thumbnail pipeline"),
+            layout: Some(&thumbnail_pipeline_layout),
+            module: &thumbnail_shader,
+            entry_point: Some("thumbnail"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let image_uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("image uniform buffer"),
+            size: [
+                size_of::<TransformUniformBufferObject>(),
+                size_of::<AdjustmentUniformBufferObject>(),
+                size_of::<ConvolutionUniformBufferObject>(),
+                size_of::<ThumbnailUniformBufferObject>(),
+            ]
+            .into_iter()
+            .max()
+            .unwrap_or_default() as BufferAddress,
+            usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+        let [back_to_front_bind_group, back_to_filter_bind_group, filter_to_front_bind_group] =
+            [(&back_texture, &front_texture), (&back_texture, &filter_texture), (&filter_texture, &front_texture)].map(
+                |(source, destination)| {
+                    Self::create_image_bind_group(
+                        &device,
+                        &image_bind_group_layout,
+                        &image_uniform_buffer,
+                        source,
+                        &back_texture,
+                        destination,
+                    )
+                },
+            );
+        let thumbnail_bind_group = Self::create_image_bind_group(
+            &device,
+            &thumbnail_bind_group_layout,
+            &image_uniform_buffer,
+            &front_texture,
+            &back_texture,
+            &thumbnail_texture,
+        );
+        let render_shader = Self::create_shader_module(
+            &device,
+            canvas_format,
+            "render shader",
+            concat!(include_str!("shaders/shapes.wgsl"), "\n", include_str!("shaders/render.wgsl")),
+        );
+        #[allow(clippy::cast_precision_loss)]
+        let vertex_uniform_buffer_object = VertexUniformBufferObject {
+            window_size: Vec2::new(window_size.width as f32, window_size.height as f32),
+            canvas_size: Vec2::new(texture_size.width as f32, texture_size.height as f32),
+            tile_size: Vec2::new(tiling.tile_size.width as f32, tiling.tile_size.height as f32),
+            ..Default::default()
+        };
         let vertex_uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("vertex uniform buffer"),
             contents: bytemuck::cast_slice(&[vertex_uniform_buffer_object]),
@@ -415,26 +774,12 @@ impl Renderer {
             contents: bytemuck::cast_slice(&[fragment_uniform_buffer_object]),
             usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
         });
-        let sampler = device.create_sampler(&SamplerDescriptor {
-            label: Some("sampler"),
-            address_mode_u: AddressMode::ClampToEdge,
-            address_mode_v: AddressMode::ClampToEdge,
-            address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: FilterMode::Nearest,
-            min_filter: FilterMode::Nearest,
-            mipmap_filter: FilterMode::Nearest,
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 1.0,
-            compare: None,
-            anisotropy_clamp: 1,
-            border_color: None,
-        });
         let render_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("render bind group layout"),
             entries: &[
                 BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: ShaderStages::VERTEX,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
                     ty: BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -469,47 +814,23 @@ impl Renderer {
                     visibility: ShaderStages::FRAGMENT,
                     ty: BindingType::Texture {
                         sample_type: TextureSampleType::Float {
-                            filterable: true,
+                            filterable: false,
                         },
                         multisampled: false,
-                        view_dimension: TextureViewDimension::D2,
+                        view_dimension: TextureViewDimension::D2Array,
                     },
                     count: None,
                 },
-                BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        });
-        let render_bind_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("render bind group"),
-            layout: &render_bind_group_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: vertex_uniform_buffer.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: storage_buffer.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 2,
-                    resource: fragment_uniform_buffer.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 3,
-                    resource: BindingResource::TextureView(&front_texture_view),
-                },
-                BindGroupEntry {
-                    binding: 4,
-                    resource: BindingResource::Sampler(&sampler),
-                },
             ],
         });
+        let render_bind_group = Self::create_render_bind_group(
+            &device,
+            &render_bind_group_layout,
+            &vertex_uniform_buffer,
+            &storage_buffer,
+            &fragment_uniform_buffer,
+            &front_texture,
+        );
         // TODO: Use a triangle and add clip rect.
         // #[rustfmt::skip]
         // let vertex_data: [[f32; 2]; 3] = [
@@ -541,6 +862,21 @@ impl Renderer {
                 shader_location: 0,
             }],
         };
+        let tile_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("tile buffer"),
+            size: u64::from(limits.max_texture_array_layers) * size_of::<u32>() as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+        let tile_buffer_layout = VertexBufferLayout {
+            array_stride: size_of::<u32>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[VertexAttribute {
+                format: VertexFormat::Uint32,
+                offset: 0,
+                shader_location: 1,
+            }],
+        };
         let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("render pipeline layout"),
             bind_group_layouts: &[&render_bind_group_layout],
@@ -560,7 +896,7 @@ impl Renderer {
                 module: &render_shader,
                 entry_point: Some("vertex"),
                 compilation_options: PipelineCompilationOptions::default(),
-                buffers: &[vertex_buffer_layout],
+                buffers: &[vertex_buffer_layout, tile_buffer_layout],
             },
             primitive: PrimitiveState::default(),
             depth_stencil: None,
@@ -578,13 +914,14 @@ impl Renderer {
             multiview: None,
             cache: None,
         });
-        let fill_buffer_bytes_per_row = (texture_size.width * 4).div_ceil(256) * 256;
-        let fill_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("fill buffer"),
-            size: u64::from(fill_buffer_bytes_per_row * texture_size.height),
-            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        let hover_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("hover buffer"),
+            size: u64::from(canvas_format.pixel_size()),
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
+        let (fill_buffer_bytes_per_row, fill_buffer) =
+            Self::create_fill_buffer(&device, tiling.tile_size, canvas_format.pixel_size());
         let renderer = Self {
             window,
             window_size,
@@ -593,16 +930,28 @@ impl Renderer {
             surface,
             texture_size,
             texture_extent,
+            tiling,
             texture_format,
+            canvas_format,
             present_mode,
             back_texture,
             front_texture,
+            filter_texture,
+            text_mask_texture,
+            thumbnail_texture,
+            text_mask_region: None,
+            dirty_region: None,
+            mipmap_dirty_region: Some((Origin3d::ZERO, texture_extent)),
+            thumbnail_outdated: true,
             storage_buffer_object,
             storage_buffer_object_changed: false,
             storage_buffer,
+            max_points,
+            points_truncated: false,
             compute_uniform_buffer_object,
             compute_uniform_buffer_object_changed: false,
             compute_uniform_buffer,
+            compute_bind_group_layout,
             compute_bind_group,
             compute_pipeline,
             vertex_uniform_buffer_object,
@@ -611,41 +960,733 @@ impl Renderer {
             fragment_uniform_buffer_object,
             fragment_uniform_buffer_object_changed: false,
             fragment_uniform_buffer,
+            render_bind_group_layout,
             render_bind_group,
+            linear_filtering: false,
+            mipmap_bind_group_layout,
+            mipmap_pipeline,
+            mipmap_bind_groups,
+            mipmap_layer_buffer,
+            image_uniform_buffer,
+            image_bind_group_layout,
+            thumbnail_bind_group_layout,
+            back_to_front_bind_group,
+            back_to_filter_bind_group,
+            filter_to_front_bind_group,
+            thumbnail_bind_group,
+            transform_pipeline,
+            adjustment_pipeline,
+            convolution_pipeline,
+            thumbnail_pipeline,
             vertex_buffer,
+            tile_buffer,
             render_pipeline,
             fill_buffer_bytes_per_row,
             fill_buffer,
+            hover_buffer,
+            hover_readback: None,
+            undo_history: VecDeque::new(),
+            redo_history: Vec::new(),
+            history_size: if adapter.get_info().device_type == DeviceType::DiscreteGpu {
+                HISTORY_SIZE
+            } else {
+                INTEGRATED_HISTORY_SIZE
+            },
         };
         renderer.configure_surface();
         Ok(renderer)
     }
 
-    fn configure_surface(&self) {
-        self.surface.configure(&self.device, &SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
-            format: self.texture_format,
-            width: self.window_size.width,
-            height: self.window_size.height,
-            present_mode: self.present_mode,
-            desired_maximum_frame_latency: 2,
-            alpha_mode: CompositeAlphaMode::Auto,
-            view_formats: vec![],
-        });
+    /// Image passes read a source texture and the back texture and write a whole texture.
+    fn create_image_bind_group_layout(
+        device: &Device,
+        label: &str,
+        destination_format: TextureFormat,
+    ) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: false,
+                        },
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: destination_format,
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: false,
+                        },
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+            ],
+        })
     }
 
-    pub fn resize_window(&mut self, size: Size<u32>, zoom: f32, offset: Position<f32>) {
-        self.window_size = size;
-        self.configure_surface();
+    /// Prepends the helpers shared by the shaders and replaces `CANVAS_FORMAT` by the storage format of the canvas.
+    fn create_shader_module(device: &Device, canvas_format: CanvasFormat, label: &str, source: &str) -> ShaderModule {
+        let source = format!(
+            "const SRGB_CANVAS = {};\n{}\n{source}",
+            canvas_format.srgb_encoded(),
+            include_str!("shaders/common.wgsl")
+        );
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(label),
+            source: ShaderSource::Wgsl(source.replace("CANVAS_FORMAT", canvas_format.storage_format()).into()),
+        })
+    }
+
+    /// Creates the back, front, text mask and filter textures, each tile of the canvas is a layer.
+    fn create_textures(
+        device: &Device,
+        texture_format: TextureFormat,
+        texture_extent: Extent3d,
+    ) -> (Texture, Texture, Texture, Texture) {
+        let back_texture = device.create_texture(&TextureDescriptor {
+            label: Some("back texture"),
+            size: texture_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: texture_format,
+            usage: TextureUsages::COPY_SRC | TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let front_texture = device.create_texture(&TextureDescriptor {
+            label: Some("front texture"),
+            size: texture_extent,
+            mip_level_count: texture_extent.max_mips(TextureDimension::D2),
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: texture_format,
+            usage: TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+        let text_mask_texture = device.create_texture(&TextureDescriptor {
+            label: Some("text mask texture"),
+            size: texture_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R8Unorm,
+            usage: TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        // Holds the result of the first pass of separable filters.
+        let filter_texture = device.create_texture(&TextureDescriptor {
+            label: Some("filter texture"),
+            size: texture_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: texture_format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+        (back_texture, front_texture, text_mask_texture, filter_texture)
+    }
+
+    fn create_storage_buffer(device: &Device, points: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("storage buffer"),
+            size: (StorageBufferObject::HEADER_SIZE + points * size_of::<Vec2>()) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_compute_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        storage_buffer: &Buffer,
+        compute_uniform_buffer: &Buffer,
+        front_texture: &Texture,
+        text_mask_texture: &Texture,
+    ) -> BindGroup {
+        let front_texture_view = front_texture.create_view(&TextureViewDescriptor {
+            label: Some("front texture view"),
+            dimension: Some(TextureViewDimension::D2Array),
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+        let text_mask_texture_view = text_mask_texture.create_view(&TextureViewDescriptor {
+            label: Some("text mask texture view"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("compute bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: storage_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: compute_uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&front_texture_view),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&text_mask_texture_view),
+                },
+            ],
+        })
+    }
+
+    /// Each bind group downsamples one mip level of the front texture into the next one.
+    fn create_mipmap_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        front_texture: &Texture,
+        layer_buffer: &Buffer,
+    ) -> Vec<BindGroup> {
+        let mip_views: Vec<_> = (0..front_texture.mip_level_count())
+            .map(|mip_level| {
+                front_texture.create_view(&TextureViewDescriptor {
+                    label: Some("front texture mip view"),
+                    dimension: Some(TextureViewDimension::D2Array),
+                    base_mip_level: mip_level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        mip_views
+            .windows(2)
+            .map(|views| {
+                device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("mipmap bind group"),
+                    layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&views[0]),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::TextureView(&views[1]),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: layer_buffer.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect()
+    }
+
+    fn create_render_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        vertex_uniform_buffer: &Buffer,
+        storage_buffer: &Buffer,
+        fragment_uniform_buffer: &Buffer,
+        front_texture: &Texture,
+    ) -> BindGroup {
+        let front_texture_view = front_texture.create_view(&TextureViewDescriptor {
+            label: Some("front texture mipmapped view"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("render bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: vertex_uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: storage_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: fragment_uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&front_texture_view),
+                },
+            ],
+        })
+    }
+
+    /// Binds the shared uniform buffer, the `source` texture and the unchanged back texture for reading and the first
+    /// mip level of `destination` for writing.
+    fn create_image_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        uniform_buffer: &Buffer,
+        source: &Texture,
+        back_texture: &Texture,
+        destination: &Texture,
+    ) -> BindGroup {
+        let source_view = source.create_view(&TextureViewDescriptor {
+            label: Some("image source view"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let original_view = back_texture.create_view(&TextureViewDescriptor {
+            label: Some("image original view"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let destination_view = destination.create_view(&TextureViewDescriptor {
+            label: Some("image destination view"),
+            dimension: Some(TextureViewDimension::D2Array),
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("image bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&source_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&destination_view),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&original_view),
+                },
+            ],
+        })
+    }
+
+    /// Creates the navigator thumbnail, a single layer so that the image passes can write it.
+    fn create_thumbnail_texture(device: &Device, texture_size: Size<u32>) -> Texture {
+        let longer_side = texture_size.width.max(texture_size.height);
+        let size = if longer_side <= THUMBNAIL_SIZE {
+            texture_size
+        } else {
+            Size::new(
+                (texture_size.width * THUMBNAIL_SIZE / longer_side).max(1),
+                (texture_size.height * THUMBNAIL_SIZE / longer_side).max(1),
+            )
+        };
+        device.create_texture(&TextureDescriptor {
+            label: Some("thumbnail texture"),
+            size: Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+            view_formats: &[TextureFormat::Rgba8UnormSrgb],
+        })
+    }
+
+    /// Returns the number of bytes per row, which has to be aligned to 256 bytes, and the buffer.
+    /// The fill buffer holds a single tile, which bounds the memory of the read backs on large canvases.
+    fn create_fill_buffer(device: &Device, tile_size: Size<u32>, pixel_size: u32) -> (u32, Buffer) {
+        let fill_buffer_bytes_per_row = (tile_size.width * pixel_size).div_ceil(256) * 256;
+        let fill_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("fill buffer"),
+            size: u64::from(fill_buffer_bytes_per_row) * u64::from(tile_size.height),
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        (fill_buffer_bytes_per_row, fill_buffer)
+    }
+
+    fn configure_surface(&self) {
+        self.surface.configure(&self.device, &SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: self.texture_format,
+            width: self.window_size.width,
+            height: self.window_size.height,
+            present_mode: self.present_mode,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        });
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn resize_window(&mut self, size: Size<u32>, zoom: f32, offset: Position<f32>) {
+        self.window_size = size;
+        self.vertex_uniform_buffer_object.window_size = Vec2::new(size.width as f32, size.height as f32);
+        self.configure_surface();
         self.scale_texture(zoom);
-        self.vertex_uniform_buffer_object.offset = Vec2::new(offset.x, offset.y); // TODO: Trait. This doesn't need to change!
+        self.vertex_uniform_buffer_object.offset = Vec2::new(offset.x, offset.y) * 0.01; // TODO: Trait. This doesn't need to change!
         self.vertex_uniform_buffer_object_changed = true;
         self.window.request_redraw();
     }
 
-    // pub fn resize_texture(&mut self, size: Size<u32>) {
-    //     todo!();
-    // }
+    /// Recreates the textures with the new size, their content is lost.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn resize_texture(&mut self, size: Size<u32>) {
+        self.texture_size = size;
+        self.texture_extent = Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+        self.tiling = Tiling::new(size, self.device.limits().max_texture_dimension_2d);
+        (self.back_texture, self.front_texture, self.text_mask_texture, self.filter_texture) =
+            Self::create_textures(&self.device, self.canvas_format.texture_format(), self.tiling.extent());
+        self.thumbnail_texture = Self::create_thumbnail_texture(&self.device, size);
+        self.compute_uniform_buffer_object.canvas_size = UVec2::new(size.width, size.height);
+        self.vertex_uniform_buffer_object.canvas_size = Vec2::new(size.width as f32, size.height as f32);
+        self.vertex_uniform_buffer_object.tile_size =
+            Vec2::new(self.tiling.tile_size.width as f32, self.tiling.tile_size.height as f32);
+        self.vertex_uniform_buffer_object_changed = true;
+        self.text_mask_region = None;
+        self.dirty_region = None;
+        self.mipmap_dirty_region = Some((Origin3d::ZERO, self.texture_extent));
+        self.thumbnail_outdated = true;
+        self.mipmap_bind_groups = Self::create_mipmap_bind_groups(
+            &self.device,
+            &self.mipmap_bind_group_layout,
+            &self.front_texture,
+            &self.mipmap_layer_buffer,
+        );
+        self.recreate_bind_groups();
+        (self.fill_buffer_bytes_per_row, self.fill_buffer) =
+            Self::create_fill_buffer(&self.device, self.tiling.tile_size, self.canvas_format.pixel_size());
+    }
+
+    /// Recreates the bind groups using the textures or the storage buffer after any of them was replaced.
+    fn recreate_bind_groups(&mut self) {
+        [self.back_to_front_bind_group, self.back_to_filter_bind_group, self.filter_to_front_bind_group] = [
+            (&self.back_texture, &self.front_texture),
+            (&self.back_texture, &self.filter_texture),
+            (&self.filter_texture, &self.front_texture),
+        ]
+        .map(|(source, destination)| {
+            Self::create_image_bind_group(
+                &self.device,
+                &self.image_bind_group_layout,
+                &self.image_uniform_buffer,
+                source,
+                &self.back_texture,
+                destination,
+            )
+        });
+        self.thumbnail_bind_group = Self::create_image_bind_group(
+            &self.device,
+            &self.thumbnail_bind_group_layout,
+            &self.image_uniform_buffer,
+            &self.front_texture,
+            &self.back_texture,
+            &self.thumbnail_texture,
+        );
+        self.compute_bind_group = Self::create_compute_bind_group(
+            &self.device,
+            &self.compute_bind_group_layout,
+            &self.storage_buffer,
+            &self.compute_uniform_buffer,
+            &self.front_texture,
+            &self.text_mask_texture,
+        );
+        self.render_bind_group = Self::create_render_bind_group(
+            &self.device,
+            &self.render_bind_group_layout,
+            &self.vertex_uniform_buffer,
+            &self.storage_buffer,
+            &self.fragment_uniform_buffer,
+            &self.front_texture,
+        );
+    }
+
+    /// Uploads the points, the storage buffer grows when they don't fit.
+    fn write_storage_buffer(&mut self) {
+        let bytes = self.storage_buffer_object.as_bytes(self.max_points);
+        if bytes.len() as u64 > self.storage_buffer.size() {
+            // Grow geometrically, so that long strokes don't reallocate with every point.
+            let points = ((bytes.len() - StorageBufferObject::HEADER_SIZE) / size_of::<Vec2>())
+                .next_power_of_two()
+                .min(self.max_points);
+            self.storage_buffer = Self::create_storage_buffer(&self.device, points);
+            self.recreate_bind_groups();
+        }
+        let points_truncated = self.storage_buffer_object.points.len() > self.max_points;
+        if points_truncated && !self.points_truncated {
+            log::warn!(
+                "Only the first {} of {} points are drawn, the GPU doesn't support more.",
+                self.max_points,
+                self.storage_buffer_object.points.len()
+            );
+        }
+        self.points_truncated = points_truncated;
+        self.queue.write_buffer(&self.storage_buffer, 0, &bytes);
+    }
+
+    /// Flips or rotates the committed texture, the cropping uses the rectangle selection. Uncommitted changes are
+    /// discarded.
+    pub fn transform_texture(&mut self, transform: ImageTransform) {
+        let (width, height) = (self.texture_size.width, self.texture_size.height);
+        #[allow(clippy::cast_possible_wrap)]
+        let (last_x, last_y) = (width as i32 - 1, height as i32 - 1);
+        // The source pixel is `origin + x * x_axis + y * y_axis` for the destination pixel `(x, y)`.
+        let (size, origin, x_axis, y_axis) = match transform {
+            ImageTransform::FlipHorizontal => {
+                (self.texture_size, IVec2::new(last_x, 0), IVec2::new(-1, 0), IVec2::new(0, 1))
+            }
+            ImageTransform::FlipVertical => {
+                (self.texture_size, IVec2::new(0, last_y), IVec2::new(1, 0), IVec2::new(0, -1))
+            }
+            ImageTransform::RotateClockwise => {
+                (Size::new(height, width), IVec2::new(0, last_y), IVec2::new(0, -1), IVec2::new(1, 0))
+            }
+            ImageTransform::RotateCounterclockwise => {
+                (Size::new(height, width), IVec2::new(last_x, 0), IVec2::new(0, 1), IVec2::new(-1, 0))
+            }
+            ImageTransform::Rotate180 => {
+                (self.texture_size, IVec2::new(last_x, last_y), IVec2::new(-1, 0), IVec2::new(0, -1))
+            }
+            ImageTransform::Crop => {
+                let Some((origin, extent)) = self.selection() else {
+                    return;
+                };
+                #[allow(clippy::cast_possible_wrap)]
+                let origin = IVec2::new(origin.x as i32, origin.y as i32);
+                (Size::new(extent.width, extent.height), origin, IVec2::new(1, 0), IVec2::new(0, 1))
+            }
+        };
+        let source = self.back_texture.clone();
+        let source_size = UVec2::new(width, height);
+        let source_canvas_size = self.texture_size;
+        self.resize_texture(size);
+        let transform_uniform_buffer_object = TransformUniformBufferObject {
+            origin,
+            x_axis,
+            y_axis,
+            source_size,
+            canvas_size: UVec2::new(size.width, size.height),
+        };
+        // The source is the replaced back texture, so the bind group is used only once.
+        let bind_group = Self::create_image_bind_group(
+            &self.device,
+            &self.image_bind_group_layout,
+            &self.image_uniform_buffer,
+            &source,
+            &self.back_texture,
+            &self.front_texture,
+        );
+        self.image_pass(
+            &self.transform_pipeline,
+            bytemuck::cast_slice(&[transform_uniform_buffer_object]),
+            &bind_group,
+            size,
+        );
+        self.copy_region(CopyDirection::FrontToBack, Origin3d::ZERO, self.texture_extent);
+        // The replaced back texture is kept as it is, it isn't used anywhere else.
+        self.record(Snapshot::Canvas {
+            size: source_canvas_size,
+            texture: source,
+        });
+    }
+
+    /// Previews the adjustment of the rectangle selection or the whole texture in the front texture.
+    pub fn adjust(&mut self, adjustment: Adjustment, parameters: &AdjustmentParameters) {
+        let (region_origin, region_size) = self.selection().unwrap_or((Origin3d::ZERO, self.texture_extent));
+        let curve = filter::curve_lookup_table(&parameters.curve);
+        let adjustment_uniform_buffer_object = AdjustmentUniformBufferObject {
+            region_origin: UVec2::new(region_origin.x, region_origin.y),
+            region_size: UVec2::new(region_size.width, region_size.height),
+            canvas_size: UVec2::new(self.texture_size.width, self.texture_size.height),
+            adjustment: adjustment as u32,
+            brightness: parameters.brightness * 0.01,
+            contrast: parameters.contrast * 0.01,
+            hue: parameters.hue,
+            saturation: parameters.saturation * 0.01,
+            lightness: parameters.lightness * 0.01,
+            input_black: parameters.input_black / 255.0,
+            input_white: parameters.input_white / 255.0,
+            gamma: parameters.gamma,
+            output_black: parameters.output_black / 255.0,
+            output_white: parameters.output_white / 255.0,
+            posterize_levels: parameters.posterize_levels,
+            threshold: parameters.threshold / 255.0,
+            _padding: [0; 4],
+            curve: array::from_fn(|i| Vec4::new(curve[i * 4], curve[i * 4 + 1], curve[i * 4 + 2], curve[i * 4 + 3])),
+        };
+        self.image_pass(
+            &self.adjustment_pipeline,
+            bytemuck::bytes_of(&adjustment_uniform_buffer_object),
+            &self.back_to_front_bind_group,
+            self.texture_size,
+        );
+        self.mark_dirty(region_origin, region_size);
+    }
+
+    /// Previews the convolution filter of the rectangle selection or the whole texture in the front texture.
+    pub fn convolve(&mut self, convolution: Convolution, parameters: &ConvolutionParameters) {
+        let (region_origin, region_size) = self.selection().unwrap_or((Origin3d::ZERO, self.texture_extent));
+        let mut weights = [0.0; 28];
+        weights[..25].copy_from_slice(if convolution == Convolution::Emboss {
+            &ConvolutionParameters::EMBOSS_KERNEL
+        } else {
+            &parameters.kernel
+        });
+        let mut convolution_uniform_buffer_object = ConvolutionUniformBufferObject {
+            region_origin: UVec2::new(region_origin.x, region_origin.y),
+            region_size: UVec2::new(region_size.width, region_size.height),
+            canvas_size: UVec2::new(self.texture_size.width, self.texture_size.height),
+            convolution: convolution as u32,
+            radius: parameters.radius,
+            direction: IVec2::new(1, 0),
+            #[allow(clippy::cast_precision_loss)]
+            sigma: (parameters.radius as f32 / 2.0).max(0.5),
+            amount: parameters.amount * 0.01,
+            threshold: parameters.threshold / 255.0,
+            #[allow(clippy::cast_possible_truncation)]
+            kernel_size: if convolution == Convolution::Emboss { 3 } else { parameters.kernel_size as u32 },
+            divisor: if convolution == Convolution::Emboss { 1.0 } else { parameters.divisor() },
+            bias: if convolution == Convolution::Emboss { 0.0 } else { parameters.bias },
+            last_pass: u32::from(!convolution.separable()),
+            _padding: [0; 12],
+            kernel: array::from_fn(|i| {
+                Vec4::new(weights[i * 4], weights[i * 4 + 1], weights[i * 4 + 2], weights[i * 4 + 3])
+            }),
+        };
+        if convolution.separable() {
+            self.image_pass(
+                &self.convolution_pipeline,
+                bytemuck::bytes_of(&convolution_uniform_buffer_object),
+                &self.back_to_filter_bind_group,
+                self.texture_size,
+            );
+            convolution_uniform_buffer_object.direction = IVec2::new(0, 1);
+            convolution_uniform_buffer_object.last_pass = 1;
+        }
+        let bind_group =
+            if convolution.separable() { &self.filter_to_front_bind_group } else { &self.back_to_front_bind_group };
+        self.image_pass(
+            &self.convolution_pipeline,
+            bytemuck::bytes_of(&convolution_uniform_buffer_object),
+            bind_group,
+            self.texture_size,
+        );
+        self.mark_dirty(region_origin, region_size);
+    }
+
+    /// Downsamples the front texture into the navigator thumbnail if it changed since the last time.
+    pub fn update_thumbnail(&mut self) {
+        if !self.thumbnail_outdated {
+            return;
+        }
+        self.thumbnail_outdated = false;
+        let thumbnail_uniform_buffer_object = ThumbnailUniformBufferObject {
+            canvas_size: UVec2::new(self.texture_size.width, self.texture_size.height),
+            ..Default::default()
+        };
+        self.image_pass(
+            &self.thumbnail_pipeline,
+            bytemuck::bytes_of(&thumbnail_uniform_buffer_object),
+            &self.thumbnail_bind_group,
+            Size::new(self.thumbnail_texture.width(), self.thumbnail_texture.height()),
+        );
+    }
+
+    /// Runs a compute pass over `size` pixels with the uniform buffer object written into the image uniform buffer.
+    fn image_pass(&self, pipeline: &ComputePipeline, uniform: &[u8], bind_group: &BindGroup, size: Size<u32>) {
+        self.queue.write_buffer(&self.image_uniform_buffer, 0, uniform);
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("image pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(size.width.div_ceil(8), size.height.div_ceil(8), 1);
+        }
+        self.queue.submit([encoder.finish()]);
+    }
+
+    /// Returns the bounding box of the rectangle selection clipped to the texture.
+    pub fn selection(&self) -> Option<(Origin3d, Extent3d)> {
+        let points = &self.storage_buffer_object.points;
+        if self.compute_uniform_buffer_object.action != Action::CutRectangle as u32 || points.len() < 2 {
+            return None;
+        }
+        let rotation = if points.len() >= 3 { helpers::rotation(points) } else { Vec2::unit_x() };
+        let diagonal = points[1] - points[0];
+        let half_width = rotation.x.mul_add(diagonal.x, rotation.y * diagonal.y).abs() / 2.0;
+        let half_height = rotation.x.mul_add(diagonal.y, -rotation.y * diagonal.x).abs() / 2.0;
+        let extent = Vec2::new(
+            rotation.x.abs().mul_add(half_width, rotation.y.abs() * half_height),
+            rotation.y.abs().mul_add(half_width, rotation.x.abs() * half_height),
+        );
+        let center = (points[0] + points[1]) / 2.0;
+        #[allow(clippy::cast_precision_loss)]
+        let texture_size = Vec2::new(self.texture_size.width as f32, self.texture_size.height as f32);
+        let min = (center - extent).map(f32::round).clamped(Vec2::zero(), texture_size);
+        let max = (center + extent).map(f32::round).clamped(Vec2::zero(), texture_size);
+        if min.x >= max.x || min.y >= max.y {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some((
+            Origin3d {
+                x: min.x as u32,
+                y: min.y as u32,
+                z: 0,
+            },
+            Extent3d {
+                width: (max.x - min.x) as u32,
+                height: (max.y - min.y) as u32,
+                depth_or_array_layers: 1,
+            },
+        ))
+    }
 
     pub fn scale_texture(&mut self, zoom: f32) {
         // TODO: Implement a trait to convert Size<T> to Size<U>.
@@ -660,13 +1701,61 @@ impl Renderer {
         self.vertex_uniform_buffer_object_changed = true;
     }
 
+    /// Scales the texture while keeping the point under `anchor` (in window pixels) in place.
+    pub fn zoom_at(&mut self, zoom: f32, anchor: Position<f32>) {
+        let relative = self.cursor_absolute_to_relative(anchor);
+        self.scale_texture(zoom);
+        self.pan_to(relative, anchor);
+    }
+
+    /// Returns the zoom in percent that makes the texture fit, fill or match the window pixels.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn preset_zoom(&self, preset: ZoomPreset) -> f32 {
+        // TODO: Implement a trait to convert Size<T> to Size<U>.
+        let (window_width, window_height) = (self.window_size.width as f32, self.window_size.height as f32);
+        let (texture_width, texture_height) = (self.texture_size.width as f32, self.texture_size.height as f32);
+
+        let window_ratio = window_width / window_height;
+        let texture_ratio = texture_width / texture_height;
+        // Scale of the texture at 100 % zoom, the texture then fits the window.
+        let fit_x = (texture_ratio / window_ratio).min(1.0);
+        let fit_y = (window_ratio / texture_ratio).min(1.0);
+
+        match preset {
+            ZoomPreset::Fit => 100.0,
+            ZoomPreset::ActualPixels => texture_width / window_width / fit_x * 100.0,
+            ZoomPreset::Fill => 100.0 / fit_x.min(fit_y),
+        }
+    }
+
+    /// Creates a view of the navigator thumbnail for egui, which expects sRGB encoded textures.
+    pub fn create_thumbnail_srgb_view(&self) -> TextureView {
+        self.thumbnail_texture.create_view(&TextureViewDescriptor {
+            label: Some("thumbnail sRGB view"),
+            format: Some(TextureFormat::Rgba8UnormSrgb),
+            ..Default::default()
+        })
+    }
+
+    /// Moves the texture so that `point` is in the center of the window.
+    pub fn center_on(&mut self, point: Position<f32>) {
+        self.vertex_uniform_buffer_object.offset = -self.relative_to_quad(point);
+        self.vertex_uniform_buffer_object_changed = true;
+    }
+
+    /// Returns how many window pixels one texture pixel covers.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn magnification(&self) -> f32 {
+        self.vertex_uniform_buffer_object.scale.x * self.window_size.width as f32 / self.texture_size.width as f32
+    }
+
     pub fn render_with<F>(&mut self, render_function: F) -> Result<(), Error>
     where
         F: FnOnce(&mut CommandEncoder, &TextureView, &Self),
     {
         if self.storage_buffer_object_changed {
             self.storage_buffer_object_changed = false;
-            self.queue.write_buffer(&self.storage_buffer, 0, &self.storage_buffer_object.as_bytes());
+            self.write_storage_buffer();
         }
         if self.vertex_uniform_buffer_object_changed {
             self.vertex_uniform_buffer_object_changed = false;
@@ -676,6 +1765,10 @@ impl Renderer {
                 bytemuck::cast_slice(&[self.vertex_uniform_buffer_object]),
             );
         }
+        if self.fragment_uniform_buffer_object.linear_filtering != u32::from(self.linear_filtering) {
+            self.fragment_uniform_buffer_object.linear_filtering = u32::from(self.linear_filtering);
+            self.fragment_uniform_buffer_object_changed = true;
+        }
         if self.fragment_uniform_buffer_object_changed {
             self.fragment_uniform_buffer_object_changed = false;
             self.queue.write_buffer(
@@ -698,6 +1791,31 @@ impl Renderer {
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("command encoder"),
         });
+        // Only the tiles written since the last regeneration are downsampled again.
+        if self.linear_filtering
+            && let Some((origin, extent)) = self.mipmap_dirty_region.take()
+        {
+            let layers: Vec<u32> = self.tiling.split(origin, extent).map(|(tile_origin, _, _)| tile_origin.z).collect();
+            self.queue.write_buffer(&self.mipmap_layer_buffer, 0, bytemuck::cast_slice(&layers));
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("mipmap pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.mipmap_pipeline);
+            for (mip_level, bind_group) in (1..).zip(&self.mipmap_bind_groups) {
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups(
+                    (self.tiling.tile_size.width >> mip_level).max(1).div_ceil(8),
+                    (self.tiling.tile_size.height >> mip_level).max(1).div_ceil(8),
+                    #[allow(clippy::cast_possible_truncation)]
+                    {
+                        layers.len() as u32
+                    },
+                );
+            }
+        }
+        let visible_tiles = self.visible_tiles();
+        self.queue.write_buffer(&self.tile_buffer, 0, bytemuck::cast_slice(&visible_tiles));
         {
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("render pass"),
@@ -721,9 +1839,11 @@ impl Renderer {
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.render_bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.tile_buffer.slice(..));
             // render_pass.set_scissor_rect(self.clip_rect.0, self.clip_rect.1, self.clip_rect.2, self.clip_rect.3);
             // render_pass.draw(0..3, 0..1);
-            render_pass.draw(0..6, 0..1);
+            #[allow(clippy::cast_possible_truncation)]
+            render_pass.draw(0..6, 0..visible_tiles.len() as u32);
         }
         (render_function)(&mut encoder, &current_texture_view, self);
         self.queue.submit([encoder.finish()]);
@@ -733,6 +1853,27 @@ impl Renderer {
         Ok(())
     }
 
+    /// Returns the layers of the tiles that intersect the window.
+    #[allow(clippy::cast_precision_loss)]
+    fn visible_tiles(&self) -> Vec<u32> {
+        let (window_width, window_height) = (self.window_size.width as f32, self.window_size.height as f32);
+        (0..self.tiling.layers())
+            .filter(|&layer| {
+                let (origin, extent) = self.tiling.tile(layer);
+                let (x_min, y_min) = (origin.x as f32, origin.y as f32);
+                let (x_max, y_max) = ((origin.x + extent.width) as f32, (origin.y + extent.height) as f32);
+                let corners = [(x_min, y_min), (x_max, y_min), (x_min, y_max), (x_max, y_max)].map(|(x, y)| {
+                    let absolute = self.cursor_relative_to_absolute(Position::new(x, y));
+                    Vec2::new(absolute.x, absolute.y)
+                });
+                let (min, max) = corners.iter().fold((corners[0], corners[0]), |(min, max), &corner| {
+                    (min.min_by_component(corner), max.max_by_component(corner))
+                });
+                min.x < window_width && min.y < window_height && max.x > 0.0 && max.y > 0.0
+            })
+            .collect()
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub fn render(&mut self) -> Result<(), Error> {
@@ -741,17 +1882,12 @@ impl Renderer {
 
     pub fn cursor_absolute_to_relative(&self, absolute: Position<f32>) -> Position<f32> {
         // TODO: Implement a trait to convert Size<T> to Size<U>.
-        let (window_width, window_height) = (self.window_size.width as f32, self.window_size.height as f32);
         let (texture_width, texture_height) = (self.texture_size.width as f32, self.texture_size.height as f32);
 
-        let ndc_x = (absolute.x / window_width).mul_add(2.0, -1.0);
-        let ndc_y = (absolute.y / window_height).mul_add(-2.0, 1.0);
-
-        let quad_x = (ndc_x / self.vertex_uniform_buffer_object.scale.x) - self.vertex_uniform_buffer_object.offset.x;
-        let quad_y = (ndc_y / self.vertex_uniform_buffer_object.scale.y) - self.vertex_uniform_buffer_object.offset.y;
+        let quad = self.absolute_to_view(absolute) - self.vertex_uniform_buffer_object.offset;
 
-        let uv_x = quad_x.mul_add(0.5, 0.5);
-        let uv_y = quad_y.mul_add(-0.5, 0.5);
+        let uv_x = quad.x.mul_add(0.5, 0.5);
+        let uv_y = quad.y.mul_add(-0.5, 0.5);
 
         let tex_x = uv_x * texture_width;
         let tex_y = uv_y * texture_height;
@@ -759,19 +1895,80 @@ impl Renderer {
         Position::new(tex_x, tex_y)
     }
 
+    /// Inverse of [`Renderer::cursor_absolute_to_relative`].
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cursor_relative_to_absolute(&self, relative: Position<f32>) -> Position<f32> {
+        let (window_width, window_height) = (self.window_size.width as f32, self.window_size.height as f32);
+        let half_window = Vec2::new(window_width, window_height) / 2.0;
+
+        let view = self.relative_to_quad(relative) + self.vertex_uniform_buffer_object.offset;
+        let ndc = self.vertex_uniform_buffer_object.transform
+            * (view * self.vertex_uniform_buffer_object.scale * half_window)
+            / half_window;
+
+        let absolute_x = ndc.x.mul_add(0.5, 0.5) * window_width;
+        let absolute_y = ndc.y.mul_add(-0.5, 0.5) * window_height;
+
+        Position::new(absolute_x, absolute_y)
+    }
+
+    /// Converts window pixels to quad coordinates shifted by the offset, undoing the scale, rotation and flip.
+    #[allow(clippy::cast_precision_loss)]
+    fn absolute_to_view(&self, absolute: Position<f32>) -> Vec2 {
+        let (window_width, window_height) = (self.window_size.width as f32, self.window_size.height as f32);
+        let half_window = Vec2::new(window_width, window_height) / 2.0;
+
+        let ndc =
+            Vec2::new((absolute.x / window_width).mul_add(2.0, -1.0), (absolute.y / window_height).mul_add(-2.0, 1.0));
+
+        // The view is rotated in window pixels, where both axes have the same scale.
+        self.vertex_uniform_buffer_object.transform.inversed() * (ndc * half_window)
+            / (self.vertex_uniform_buffer_object.scale * half_window)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn relative_to_quad(&self, relative: Position<f32>) -> Vec2 {
+        let (texture_width, texture_height) = (self.texture_size.width as f32, self.texture_size.height as f32);
+        Vec2::new((relative.x / texture_width).mul_add(2.0, -1.0), (relative.y / texture_height).mul_add(-2.0, 1.0))
+    }
+
+    /// Moves the texture so that `relative` (in texture pixels) is under `absolute` (in window pixels).
+    pub fn pan_to(&mut self, relative: Position<f32>, absolute: Position<f32>) {
+        self.vertex_uniform_buffer_object.offset = self.absolute_to_view(absolute) - self.relative_to_quad(relative);
+        self.vertex_uniform_buffer_object_changed = true;
+    }
+
+    /// Rotates the view around the center of the window by `degrees` counterclockwise, `flipped` mirrors it
+    /// horizontally before the rotation. Only the view is transformed, the texture stays the same.
+    pub fn set_view_transform(&mut self, degrees: f32, flipped: bool) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let flip = if flipped { -1.0 } else { 1.0 };
+        self.vertex_uniform_buffer_object.transform =
+            Mat2::new(Vec2::new(cos * flip, sin * flip), Vec2::new(-sin, cos));
+        self.vertex_uniform_buffer_object_changed = true;
+    }
+
+    /// Returns whether the texture axes are parallel to the window axes.
+    pub fn view_axis_aligned(&self) -> bool {
+        self.vertex_uniform_buffer_object.transform.cols[0].y.abs() < f32::EPSILON
+    }
+
     pub fn draw(&mut self) {
         if self.storage_buffer_object_changed {
             self.storage_buffer_object_changed = false;
-            self.queue.write_buffer(&self.storage_buffer, 0, &self.storage_buffer_object.as_bytes());
-        }
-        if self.compute_uniform_buffer_object_changed {
-            self.compute_uniform_buffer_object_changed = false;
-            self.queue.write_buffer(
-                &self.compute_uniform_buffer,
-                0,
-                bytemuck::cast_slice(&[self.compute_uniform_buffer_object]),
-            );
+            self.write_storage_buffer();
         }
+        // The origin changes with the primitive, so the uniform buffer is written every time.
+        self.compute_uniform_buffer_object_changed = false;
+        let Some((origin, extent)) = self.draw_region() else {
+            return;
+        };
+        self.compute_uniform_buffer_object.origin = UVec2::new(origin.x, origin.y);
+        self.queue.write_buffer(
+            &self.compute_uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.compute_uniform_buffer_object]),
+        );
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("command encoder"),
         });
@@ -784,18 +1981,131 @@ impl Renderer {
             compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
             // TODO: Just force a texture extent that is a multiple of 8. That would also make the id check in the
             // compute shader redundant.
-            compute_pass.dispatch_workgroups(
-                self.texture_size.width.div_ceil(8),
-                self.texture_size.height.div_ceil(8),
-                1,
-            );
+            compute_pass.dispatch_workgroups(extent.width.div_ceil(8), extent.height.div_ceil(8), 1);
         }
         self.queue.submit([encoder.finish()]);
         self.device.poll(Maintain::Wait);
+        self.mark_dirty(origin, extent);
         self.window.request_redraw();
     }
 
-    pub fn copy_texture(&self, direction: CopyDirection) {
+    /// Returns the region of the texture the primitive can cover including the stroke and anti-aliasing, or `None`
+    /// if there is nothing to draw.
+    fn draw_region(&self) -> Option<(Origin3d, Extent3d)> {
+        let uniform = &self.compute_uniform_buffer_object;
+        let is = |action: Action| uniform.action == action as u32;
+        if is(Action::Init) {
+            return Some((Origin3d::ZERO, self.texture_extent));
+        }
+        let points = &self.storage_buffer_object.points;
+        let points = &points[..(self.storage_buffer_object.length as usize).min(points.len())];
+        if points.len() < 2 {
+            return None;
+        }
+        let (mut min, mut max) = if is(Action::DrawCircle) || is(Action::DrawRegularShape) {
+            let radius = Vec2::broadcast((points[1] - points[0]).mag());
+            (points[0] - radius, points[0] + radius)
+        } else if is(Action::DrawRectangle) || is(Action::DrawEllipse) {
+            // The circumscribed circle covers any rotation.
+            let center = (points[0] + points[1]) / 2.0;
+            let radius = Vec2::broadcast((points[1] - points[0]).mag() / 2.0);
+            (center - radius, center + radius)
+        } else {
+            points.iter().fold((points[0], points[0]), |(min, max), &point| {
+                (min.min_by_component(point), max.max_by_component(point))
+            })
+        };
+        // Catmull-Rom splines can overshoot the control points by a sixth of their extent.
+        if is(Action::DrawSpline) {
+            let overshoot = (max - min) / 6.0;
+            min -= overshoot;
+            max += overshoot;
+        }
+        // Only the smoothstep transition depends on the stroke, the others stay within a pixel.
+        let smoothing = if uniform.anti_aliasing == AntiAliasing::Smoothstep as u32 {
+            uniform.anti_aliasing_scale.max(0.0)
+        } else {
+            0.0
+        };
+        let mut padding = uniform.stroke.mul_add(1.0 + smoothing, 1.0);
+        if is(Action::DrawPolyline) {
+            padding += MITER_LIMIT * uniform.stroke;
+        } else if is(Action::DrawArrow) {
+            padding += uniform.arrow_size * uniform.stroke;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let texture_size = Vec2::new(self.texture_size.width as f32, self.texture_size.height as f32);
+        let min = (min - Vec2::broadcast(padding)).map(f32::floor).clamped(Vec2::zero(), texture_size);
+        let max = (max + Vec2::broadcast(padding)).map(f32::ceil).clamped(Vec2::zero(), texture_size);
+        if min.x >= max.x || min.y >= max.y {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some((
+            Origin3d {
+                x: min.x as u32,
+                y: min.y as u32,
+                z: 0,
+            },
+            Extent3d {
+                width: (max.x - min.x) as u32,
+                height: (max.y - min.y) as u32,
+                depth_or_array_layers: 1,
+            },
+        ))
+    }
+
+    /// Extends the region that has to be copied between the front and the back texture.
+    fn mark_dirty(&mut self, origin: Origin3d, extent: Extent3d) {
+        self.dirty_region = Some(Self::union(self.dirty_region, origin, extent));
+        self.mipmap_dirty_region = Some(Self::union(self.mipmap_dirty_region, origin, extent));
+        self.thumbnail_outdated = true;
+    }
+
+    /// Returns the bounding box of `region` and the region at `origin` of `extent`.
+    fn union(region: Option<(Origin3d, Extent3d)>, origin: Origin3d, extent: Extent3d) -> (Origin3d, Extent3d) {
+        match region {
+            Some((dirty_origin, dirty_extent)) => {
+                let x = dirty_origin.x.min(origin.x);
+                let y = dirty_origin.y.min(origin.y);
+                (
+                    Origin3d {
+                        x,
+                        y,
+                        z: 0,
+                    },
+                    Extent3d {
+                        width: (dirty_origin.x + dirty_extent.width).max(origin.x + extent.width) - x,
+                        height: (dirty_origin.y + dirty_extent.height).max(origin.y + extent.height) - y,
+                        depth_or_array_layers: 1,
+                    },
+                )
+            }
+            None => (origin, extent),
+        }
+    }
+
+    /// Copies the region where the textures differ, afterwards they are the same.
+    pub fn copy_texture(&mut self, direction: CopyDirection) {
+        let Some((origin, extent)) = self.dirty_region.take() else {
+            return;
+        };
+        match direction {
+            CopyDirection::BackToFront => {
+                self.mipmap_dirty_region = Some(Self::union(self.mipmap_dirty_region, origin, extent));
+                self.thumbnail_outdated = true;
+            }
+            // Committing the changes makes them undoable.
+            CopyDirection::FrontToBack => match self.snapshot_region(origin, extent) {
+                Some(snapshot) => self.record(snapshot),
+                // Older snapshots would restore the canvas around a change that can't be undone.
+                None => self.clear_history(),
+            },
+        }
+        self.copy_region(direction, origin, extent);
+    }
+
+    fn copy_region(&self, direction: CopyDirection, origin: Origin3d, extent: Extent3d) {
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("command encoder"),
         });
@@ -803,90 +2113,530 @@ impl Renderer {
             CopyDirection::BackToFront => (&self.back_texture, &self.front_texture),
             CopyDirection::FrontToBack => (&self.front_texture, &self.back_texture),
         };
-        // TODO: Copy only the smallet part that needs to be copied.
-        encoder.copy_texture_to_texture(source.as_image_copy(), destination.as_image_copy(), self.texture_extent);
+        for (tile_origin, _, tile_extent) in self.tiling.split(origin, extent) {
+            encoder.copy_texture_to_texture(
+                TexelCopyTextureInfo {
+                    texture: source,
+                    mip_level: 0,
+                    origin: tile_origin,
+                    aspect: TextureAspect::All,
+                },
+                TexelCopyTextureInfo {
+                    texture: destination,
+                    mip_level: 0,
+                    origin: tile_origin,
+                    aspect: TextureAspect::All,
+                },
+                tile_extent,
+            );
+        }
+        self.queue.submit([encoder.finish()]);
+        self.device.poll(Maintain::Wait);
+    }
+
+    /// Copies the region of the back texture into a new snapshot, which is only as large as the largest part of the
+    /// region in a tile. Returns `None` if there isn't enough memory left for it.
+    fn snapshot_region(&self, origin: Origin3d, extent: Extent3d) -> Option<Snapshot> {
+        let size = self.tiling.split(origin, extent).fold(
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 0,
+            },
+            |size, (_, _, tile_extent)| Extent3d {
+                width: size.width.max(tile_extent.width),
+                height: size.height.max(tile_extent.height),
+                depth_or_array_layers: size.depth_or_array_layers + 1,
+            },
+        );
+        self.device.push_error_scope(ErrorFilter::OutOfMemory);
+        let texture = self.device.create_texture(&TextureDescriptor {
+            label: Some("snapshot texture"),
+            size: Extent3d {
+                depth_or_array_layers: size.depth_or_array_layers.max(1),
+                ..size
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: self.canvas_format.texture_format(),
+            usage: TextureUsages::COPY_SRC | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        if let Some(error) = pollster::block_on(self.device.pop_error_scope()) {
+            log::warn!("Failed to allocate an undo snapshot: {error}");
+            return None;
+        }
+        self.copy_snapshot(&texture, origin, extent, false);
+        Some(Snapshot::Region {
+            origin,
+            extent,
+            texture,
+        })
+    }
+
+    /// Copies the region between the back texture and the snapshot texture, in the opposite direction when
+    /// `restore` is set. Each part of the region in a tile is in its own layer of the snapshot.
+    fn copy_snapshot(&self, texture: &Texture, origin: Origin3d, extent: Extent3d, restore: bool) {
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        for ((tile_origin, _, tile_extent), layer) in self.tiling.split(origin, extent).zip(0..) {
+            let mut canvas = TexelCopyTextureInfo {
+                texture: &self.back_texture,
+                mip_level: 0,
+                origin: tile_origin,
+                aspect: TextureAspect::All,
+            };
+            let mut snapshot = TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer,
+                },
+                aspect: TextureAspect::All,
+            };
+            if restore {
+                (canvas, snapshot) = (snapshot, canvas);
+            }
+            encoder.copy_texture_to_texture(canvas, snapshot, tile_extent);
+        }
         self.queue.submit([encoder.finish()]);
+    }
+
+    /// Adds the snapshot to the undo history, which drops the redo history.
+    fn record(&mut self, snapshot: Snapshot) {
+        self.undo_history.push_back(snapshot);
+        self.redo_history.clear();
+        let mut size: u64 = self.undo_history.iter().map(|snapshot| self.snapshot_size(snapshot)).sum();
+        // The last snapshot is kept even if it's over the limit on its own.
+        while size > self.history_size && self.undo_history.len() > 1 {
+            if let Some(snapshot) = self.undo_history.pop_front() {
+                size -= self.snapshot_size(&snapshot);
+            }
+        }
+    }
+
+    fn snapshot_size(&self, snapshot: &Snapshot) -> u64 {
+        let texture = snapshot.texture();
+        u64::from(texture.width())
+            * u64::from(texture.height())
+            * u64::from(texture.depth_or_array_layers())
+            * u64::from(self.canvas_format.pixel_size())
+    }
+
+    /// Restores the committed state before the last change, uncommitted changes are discarded.
+    pub fn undo(&mut self) {
+        self.copy_texture(CopyDirection::BackToFront);
+        if let Some(snapshot) = self.undo_history.pop_back() {
+            match self.restore(snapshot) {
+                Some(snapshot) => self.redo_history.push(snapshot),
+                None => self.redo_history.clear(),
+            }
+        }
+    }
+
+    /// Restores the committed state before the last undo, uncommitted changes are discarded.
+    pub fn redo(&mut self) {
+        self.copy_texture(CopyDirection::BackToFront);
+        if let Some(snapshot) = self.redo_history.pop() {
+            match self.restore(snapshot) {
+                Some(snapshot) => self.undo_history.push_back(snapshot),
+                None => self.undo_history.clear(),
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_history.is_empty()
+    }
+
+    pub const fn can_redo(&self) -> bool {
+        !self.redo_history.is_empty()
+    }
+
+    /// Forgets all the changes, so that they can't be undone.
+    pub fn clear_history(&mut self) {
+        self.undo_history.clear();
+        self.redo_history.clear();
+    }
+
+    /// Writes the snapshot into both textures and returns the snapshot of the state it replaced, or `None` if there
+    /// isn't enough memory left for it.
+    fn restore(&mut self, snapshot: Snapshot) -> Option<Snapshot> {
+        let replaced = match snapshot {
+            Snapshot::Region {
+                origin,
+                extent,
+                texture,
+            } => {
+                let replaced = self.snapshot_region(origin, extent);
+                self.copy_snapshot(&texture, origin, extent, true);
+                self.mark_dirty(origin, extent);
+                replaced
+            }
+            Snapshot::Canvas {
+                size,
+                texture,
+            } => {
+                let replaced = Some(Snapshot::Canvas {
+                    size: self.texture_size,
+                    texture: self.back_texture.clone(),
+                });
+                self.resize_texture(size);
+                self.back_texture = texture;
+                self.recreate_bind_groups();
+                self.mark_dirty(Origin3d::ZERO, self.texture_extent);
+                replaced
+            }
+        };
+        self.copy_texture(CopyDirection::BackToFront);
+        replaced
+    }
+
+    /// Flood fills the area of the same color as the pixel at `position` with the straight sRGB `color`. Only the
+    /// tiles that the area reaches are read back.
+    pub fn fill(&mut self, position: Position<u32>, color: [u8; 4]) {
+        if position.x >= self.texture_size.width || position.y >= self.texture_size.height {
+            return;
+        }
+        let mut tiles = HashMap::new();
+        let Some((min, max)) = self.flood_fill(&mut tiles, position, &self.canvas_format.encode(color)) else {
+            return;
+        };
+        for (layer, (pixels, changed)) in tiles {
+            if !changed {
+                continue;
+            }
+            let (_, extent) = self.tiling.tile(layer);
+            self.queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &self.front_texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                &pixels,
+                self.fill_buffer_layout(Origin3d::ZERO),
+                extent,
+            );
+        }
+        self.queue.submit([]);
         self.device.poll(Maintain::Wait);
+        self.mark_dirty(
+            Origin3d {
+                x: min.x,
+                y: min.y,
+                z: 0,
+            },
+            Extent3d {
+                width: max.x - min.x + 1,
+                height: max.y - min.y + 1,
+                depth_or_array_layers: 1,
+            },
+        );
     }
 
-    pub fn fill(&self, position: Position<u32>, color: [u8; 4]) {
+    /// Reads the tile in `layer` back through the fill buffer, the rows keep the stride of the fill buffer.
+    fn read_fill_tile(&self, layer: u32) -> Vec<u8> {
+        let (_, extent) = self.tiling.tile(layer);
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("command encoder"),
         });
         encoder.copy_texture_to_buffer(
-            self.front_texture.as_image_copy(),
+            TexelCopyTextureInfo {
+                texture: &self.front_texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer,
+                },
+                aspect: TextureAspect::All,
+            },
             TexelCopyBufferInfo {
                 buffer: &self.fill_buffer,
-                // https://docs.rs/wgpu/latest/wgpu/struct.TexelCopyBufferLayout.html
-                layout: TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(self.fill_buffer_bytes_per_row),
-                    rows_per_image: None,
-                },
+                layout: self.fill_buffer_layout(Origin3d::ZERO),
             },
-            self.texture_extent,
+            extent,
         );
         self.queue.submit([encoder.finish()]);
         self.device.poll(Maintain::Wait);
         self.fill_buffer.slice(..).map_async(MapMode::Read, |_| ());
         self.device.poll(Maintain::Wait);
-        {
-            let mut buffer = self.fill_buffer.slice(..).get_mapped_range_mut();
-            self.flood_fill(&mut buffer, self.texture_size, position, color);
-        }
+        let pixels = self.fill_buffer.slice(..).get_mapped_range().to_vec();
         self.fill_buffer.unmap();
-        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("command encoder"),
-        });
-        encoder.copy_buffer_to_texture(
-            TexelCopyBufferInfo {
-                buffer: &self.fill_buffer,
-                // https://docs.rs/wgpu/latest/wgpu/struct.TexelCopyBufferLayout.html
-                layout: TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(self.fill_buffer_bytes_per_row),
+        pixels
+    }
+
+    fn fill_buffer_layout(&self, origin: Origin3d) -> TexelCopyBufferLayout {
+        // https://docs.rs/wgpu/latest/wgpu/struct.TexelCopyBufferLayout.html
+        TexelCopyBufferLayout {
+            offset: u64::from(origin.y) * u64::from(self.fill_buffer_bytes_per_row)
+                + u64::from(origin.x) * u64::from(self.canvas_format.pixel_size()),
+            bytes_per_row: Some(self.fill_buffer_bytes_per_row),
+            rows_per_image: None,
+        }
+    }
+
+    /// Writes `data` with `extent.width` bytes per row into the region of the text mask at `origin`.
+    fn write_text_mask_region(&self, origin: Origin3d, extent: Extent3d, data: &[u8]) {
+        for (tile_origin, canvas_origin, tile_extent) in self.tiling.split(origin, extent) {
+            self.queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &self.text_mask_texture,
+                    mip_level: 0,
+                    origin: tile_origin,
+                    aspect: TextureAspect::All,
+                },
+                data,
+                TexelCopyBufferLayout {
+                    offset: u64::from(canvas_origin.y - origin.y) * u64::from(extent.width)
+                        + u64::from(canvas_origin.x - origin.x),
+                    bytes_per_row: Some(extent.width),
                     rows_per_image: None,
                 },
+                tile_extent,
+            );
+        }
+    }
+
+    /// Replaces the text mask composited by [`Action::DrawText`](crate::helpers::Action::DrawText) with `coverage` of
+    /// the given size placed at `position`, the parts outside of the canvas are clipped.
+    pub fn write_text_mask(&mut self, position: Position<i32>, size: Size<u32>, coverage: &[u8]) {
+        if let Some((origin, extent)) = self.text_mask_region.take() {
+            self.write_text_mask_region(origin, extent, &vec![0; (extent.width * extent.height) as usize]);
+        }
+        // TODO: Implement a trait to convert Size<T> to Size<U>.
+        #[allow(clippy::cast_possible_wrap)]
+        let (texture_width, texture_height) = (self.texture_size.width as i32, self.texture_size.height as i32);
+        #[allow(clippy::cast_possible_wrap)]
+        let (x_min, y_min, x_max, y_max) = (
+            position.x.clamp(0, texture_width),
+            position.y.clamp(0, texture_height),
+            (position.x + size.width as i32).clamp(0, texture_width),
+            (position.y + size.height as i32).clamp(0, texture_height),
+        );
+        if x_min >= x_max || y_min >= y_max {
+            return;
+        }
+        #[allow(clippy::cast_sign_loss)]
+        let (origin, extent) = (
+            Origin3d {
+                x: x_min as u32,
+                y: y_min as u32,
+                z: 0,
+            },
+            Extent3d {
+                width: (x_max - x_min) as u32,
+                height: (y_max - y_min) as u32,
+                depth_or_array_layers: 1,
             },
-            self.front_texture.as_image_copy(),
-            self.texture_extent,
         );
+        #[allow(clippy::cast_sign_loss)]
+        let clipped: Vec<_> = (y_min..y_max)
+            .flat_map(|y| {
+                let row = (y - position.y) as usize * size.width as usize;
+                &coverage[row + (x_min - position.x) as usize..row + (x_max - position.x) as usize]
+            })
+            .copied()
+            .collect();
+        self.write_text_mask_region(origin, extent, &clipped);
+        self.text_mask_region = Some((origin, extent));
+    }
+
+    /// Starts reading back the color of the pixel at `position`, which [`Self::hovered_color`] returns on a later
+    /// frame. Returns `false` while the previous read back is still in progress.
+    pub fn read_hovered_color(&mut self, position: Position<u32>) -> bool {
+        if self.hover_readback.is_some() {
+            return false;
+        }
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        let origin = Origin3d {
+            x: position.x,
+            y: position.y,
+            z: 0,
+        };
+        let extent = Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+        for (tile_origin, _, tile_extent) in self.tiling.split(origin, extent) {
+            encoder.copy_texture_to_buffer(
+                TexelCopyTextureInfo {
+                    texture: &self.front_texture,
+                    mip_level: 0,
+                    origin: tile_origin,
+                    aspect: TextureAspect::All,
+                },
+                TexelCopyBufferInfo {
+                    buffer: &self.hover_buffer,
+                    layout: TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: None,
+                        rows_per_image: None,
+                    },
+                },
+                tile_extent,
+            );
+        }
+        self.queue.submit([encoder.finish()]);
+        let (sender, receiver) = mpsc::channel();
+        self.hover_buffer.slice(..).map_async(MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        self.hover_readback = Some(receiver);
+        true
+    }
+
+    /// Returns the color read back by [`Self::read_hovered_color`] once it's available.
+    pub fn hovered_color(&mut self) -> Option<[u8; 4]> {
+        let receiver = self.hover_readback.as_ref()?;
+        self.device.poll(Maintain::Poll);
+        let result = receiver.try_recv().ok()?;
+        self.hover_readback = None;
+        result.ok()?;
+        let color = color::to_srgb(self.canvas_format.decode(&self.hover_buffer.slice(..).get_mapped_range()));
+        self.hover_buffer.unmap();
+        Some(color)
+    }
+
+    pub const fn reading_hovered_color(&self) -> bool {
+        self.hover_readback.is_some()
+    }
+
+    /// Returns the color under `position` averaged over a square with the side of `2 * radius + 1` pixels.
+    pub fn pick(&self, position: Position<u32>, radius: u32) -> Option<[u8; 4]> {
+        if position.x >= self.texture_size.width || position.y >= self.texture_size.height {
+            return None;
+        }
+        let origin = Origin3d {
+            x: position.x.saturating_sub(radius),
+            y: position.y.saturating_sub(radius),
+            z: 0,
+        };
+        let extent = Extent3d {
+            width: (position.x + radius).min(self.texture_size.width - 1) - origin.x + 1,
+            height: (position.y + radius).min(self.texture_size.height - 1) - origin.y + 1,
+            depth_or_array_layers: 1,
+        };
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("command encoder"),
+        });
+        for (tile_origin, canvas_origin, tile_extent) in self.tiling.split(origin, extent) {
+            encoder.copy_texture_to_buffer(
+                TexelCopyTextureInfo {
+                    texture: &self.front_texture,
+                    mip_level: 0,
+                    origin: tile_origin,
+                    aspect: TextureAspect::All,
+                },
+                TexelCopyBufferInfo {
+                    buffer: &self.fill_buffer,
+                    layout: self.fill_buffer_layout(Origin3d {
+                        x: canvas_origin.x - origin.x,
+                        y: canvas_origin.y - origin.y,
+                        z: 0,
+                    }),
+                },
+                tile_extent,
+            );
+        }
         self.queue.submit([encoder.finish()]);
         self.device.poll(Maintain::Wait);
+        self.fill_buffer.slice(..).map_async(MapMode::Read, |_| ());
+        self.device.poll(Maintain::Wait);
+        // The colors are premultiplied, so that transparent pixels don't darken the result.
+        let mut sum = [0.0; 4];
+        {
+            let buffer = self.fill_buffer.slice(..).get_mapped_range();
+            let pixel_size = self.canvas_format.pixel_size() as usize;
+            for y in 0..extent.height {
+                for x in 0..extent.width {
+                    let idx = y as usize * self.fill_buffer_bytes_per_row as usize + x as usize * pixel_size;
+                    for (channel, value) in
+                        sum.iter_mut().zip(self.canvas_format.decode(&buffer[idx..idx + pixel_size]))
+                    {
+                        *channel += value;
+                    }
+                }
+            }
+        }
+        self.fill_buffer.unmap();
+        #[allow(clippy::cast_precision_loss)]
+        let pixels = (extent.width * extent.height) as f32;
+        Some(color::to_srgb(sum.map(|channel| channel / pixels)))
     }
 
     // TODO: Clean-up!
-    fn flood_fill(&self, buffer: &mut [u8], texture_size: Size<u32>, position: Position<u32>, new_color: [u8; 4]) {
-        if position.x >= texture_size.width || position.y >= texture_size.height {
-            return;
-        }
+    /// Fills the pixels of the tiles, which are read back when the area first reaches them and marked as changed
+    /// when any of their pixels is filled. Returns the bounding box of the filled pixels.
+    fn flood_fill(
+        &self,
+        tiles: &mut HashMap<u32, (Vec<u8>, bool)>,
+        position: Position<u32>,
+        new_color: &[u8],
+    ) -> Option<(Position<u32>, Position<u32>)> {
+        let pixel_size = new_color.len();
+        let Size {
+            width: tile_width,
+            height: tile_height,
+        } = self.tiling.tile_size;
+        // Returns the layer of the tile with the pixel and the index of the pixel in it.
+        let locate = |x: u32, y: u32| {
+            (
+                y / tile_height * self.tiling.columns + x / tile_width,
+                (y % tile_height) as usize * self.fill_buffer_bytes_per_row as usize
+                    + (x % tile_width) as usize * pixel_size,
+            )
+        };
 
-        let start_idx = (position.y * self.fill_buffer_bytes_per_row + position.x * 4) as usize;
-        let target_color: [u8; 4] = buffer[start_idx..start_idx + 4].try_into().unwrap();
+        let (layer, start_idx) = locate(position.x, position.y);
+        let (pixels, _) = tiles.entry(layer).or_insert_with(|| (self.read_fill_tile(layer), false));
+        let target_color = pixels[start_idx..start_idx + pixel_size].to_vec();
 
         if target_color == new_color {
-            return;
+            return None;
         }
 
+        let (mut min, mut max) = (position, position);
         let mut queue = VecDeque::new();
-        queue.push_back((position.x as i32, position.y as i32));
+        queue.push_back((position.x, position.y));
 
         while let Some((x, y)) = queue.pop_front() {
-            if x < 0 || y < 0 || x >= texture_size.width as i32 || y >= texture_size.height as i32 {
+            let (layer, idx) = locate(x, y);
+            let (pixels, changed) = tiles.entry(layer).or_insert_with(|| (self.read_fill_tile(layer), false));
+            if pixels[idx..idx + pixel_size] != target_color {
                 continue;
             }
 
-            let idx = (y as u32 * self.fill_buffer_bytes_per_row + x as u32 * 4) as usize;
-            if buffer[idx..idx + 4] != target_color {
-                continue;
-            }
+            pixels[idx..idx + pixel_size].copy_from_slice(new_color);
+            *changed = true;
+            min = Position::new(min.x.min(x), min.y.min(y));
+            max = Position::new(max.x.max(x), max.y.max(y));
 
-            buffer[idx..idx + 4].copy_from_slice(&new_color);
-
-            queue.push_back((x - 1, y));
-            queue.push_back((x + 1, y));
-            queue.push_back((x, y - 1));
-            queue.push_back((x, y + 1));
+            if x > 0 {
+                queue.push_back((x - 1, y));
+            }
+            if x + 1 < self.texture_size.width {
+                queue.push_back((x + 1, y));
+            }
+            if y > 0 {
+                queue.push_back((x, y - 1));
+            }
+            if y + 1 < self.texture_size.height {
+                queue.push_back((x, y + 1));
+            }
         }
+        Some((min, max))
     }
 }
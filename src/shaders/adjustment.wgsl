@@ -0,0 +1,138 @@
+// Adjusts the colors of the canvas inside a region.
+struct AdjustmentUniformBufferObject {
+    region_origin: vec2<u32>,
+    region_size: vec2<u32>,
+    canvas_size: vec2<u32>,
+    adjustment: u32,
+    brightness: f32,
+    contrast: f32,
+    // In degrees.
+    hue: f32,
+    saturation: f32,
+    lightness: f32,
+    input_black: f32,
+    input_white: f32,
+    gamma: f32,
+    output_black: f32,
+    output_white: f32,
+    posterize_levels: u32,
+    threshold: f32,
+    // 256 values of the curve packed into vectors.
+    curve: array<vec4<f32>, 64>,
+}
+
+@group(0) @binding(0) var<uniform> ubo: AdjustmentUniformBufferObject;
+@group(0) @binding(1) var source: texture_2d_array<f32>;
+@group(0) @binding(2) var destination: texture_storage_2d_array<CANVAS_FORMAT, write>;
+
+const LUMINANCE: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+// https://en.wikipedia.org/wiki/HSL_and_HSV#From_RGB
+fn rgb_to_hsl(color: vec3<f32>) -> vec3<f32> {
+    let maximum = max(color.r, max(color.g, color.b));
+    let minimum = min(color.r, min(color.g, color.b));
+    let chroma = maximum - minimum;
+    let lightness = (maximum + minimum) / 2.0;
+    if chroma <= 0.0 {
+        return vec3<f32>(0.0, 0.0, lightness);
+    }
+    var hue: f32;
+    if maximum == color.r {
+        hue = (color.g - color.b) / chroma;
+    } else if maximum == color.g {
+        hue = (color.b - color.r) / chroma + 2.0;
+    } else {
+        hue = (color.r - color.g) / chroma + 4.0;
+    }
+    let saturation = chroma / (1.0 - abs(2.0 * lightness - 1.0));
+    return vec3<f32>(fract(hue / 6.0), saturation, lightness);
+}
+
+// https://en.wikipedia.org/wiki/HSL_and_HSV#HSL_to_RGB_alternative
+fn hsl_to_rgb(hsl: vec3<f32>) -> vec3<f32> {
+    let k = (vec3<f32>(0.0, 8.0, 4.0) + hsl.x * 12.0) % 12.0;
+    let a = hsl.y * min(hsl.z, 1.0 - hsl.z);
+    return hsl.z - a * clamp(min(k - 3.0, 9.0 - k), vec3<f32>(-1.0), vec3<f32>(1.0));
+}
+
+fn curve(value: f32) -> f32 {
+    let position = clamp(value, 0.0, 1.0) * 255.0;
+    let i = u32(floor(position));
+    let j = min(i + 1u, 255u);
+    return mix(ubo.curve[i / 4u][i % 4u], ubo.curve[j / 4u][j % 4u], fract(position));
+}
+
+fn adjust_color(color: vec3<f32>) -> vec3<f32> {
+    switch ubo.adjustment {
+        // Brightness and contrast
+        case 0u: {
+            let contrast = clamp(ubo.contrast, -1.0, 0.99);
+            let slope = select(1.0 + contrast, 1.0 / (1.0 - contrast), contrast > 0.0);
+            return (color + ubo.brightness - 0.5) * slope + 0.5;
+        }
+        // Hue, saturation and lightness
+        case 1u: {
+            var hsl = rgb_to_hsl(color);
+            hsl.x = fract(hsl.x + ubo.hue / 360.0);
+            hsl.y = clamp(hsl.y * (1.0 + ubo.saturation), 0.0, 1.0);
+            let rgb = hsl_to_rgb(hsl);
+            if ubo.lightness > 0.0 {
+                return mix(rgb, vec3<f32>(1.0), ubo.lightness);
+            }
+            return mix(rgb, vec3<f32>(0.0), -ubo.lightness);
+        }
+        // Levels
+        case 2u: {
+            let range = max(ubo.input_white - ubo.input_black, 1e-3);
+            let value = pow(clamp((color - ubo.input_black) / range, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / ubo.gamma));
+            return mix(vec3<f32>(ubo.output_black), vec3<f32>(ubo.output_white), value);
+        }
+        // Curves
+        case 3u: {
+            return vec3<f32>(curve(color.r), curve(color.g), curve(color.b));
+        }
+        // Invert
+        case 4u: {
+            return 1.0 - color;
+        }
+        // Grayscale
+        case 5u: {
+            return vec3<f32>(dot(color, LUMINANCE));
+        }
+        // Posterize
+        case 6u: {
+            let levels = f32(max(ubo.posterize_levels, 2u) - 1u);
+            return round(color * levels) / levels;
+        }
+        // Threshold
+        case 7u: {
+            return vec3<f32>(step(ubo.threshold, dot(color, LUMINANCE)));
+        }
+        default: {
+            return color;
+        }
+    }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn adjust(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= ubo.canvas_size) {
+        return;
+    }
+    let texel = tile(textureDimensions(destination), ubo.canvas_size.x, id.xy);
+    let stored = textureLoad(source, texel.xy, texel.z, 0);
+    let local = id.xy - ubo.region_origin;
+    // The subtraction wraps around for pixels before the region origin.
+    if local.x >= ubo.region_size.x || local.y >= ubo.region_size.y {
+        textureStore(destination, texel.xy, texel.z, stored);
+        return;
+    }
+    if stored.a <= 0.0 {
+        textureStore(destination, texel.xy, texel.z, stored);
+        return;
+    }
+    let color = decode_canvas(stored);
+    // The adjustments work on straight sRGB colors, the canvas holds premultiplied colors in linear light.
+    let adjusted = clamp(adjust_color(linear_to_srgb(color.rgb / color.a)), vec3<f32>(0.0), vec3<f32>(1.0));
+    textureStore(destination, texel.xy, texel.z, encode_canvas(vec4<f32>(srgb_to_linear(adjusted) * color.a, color.a)));
+}
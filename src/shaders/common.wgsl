@@ -0,0 +1,33 @@
+// Prepended to every shader after the `SRGB_CANVAS` constant, `CANVAS_FORMAT` in the shaders is replaced by the
+// storage format of the canvas.
+
+// https://en.wikipedia.org/wiki/SRGB#Transfer_function_(%22gamma%22)
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    return select(pow((color + 0.055) / 1.055, vec3<f32>(2.4)), color / 12.92, color <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    return select(1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055, color * 12.92, color <= vec3<f32>(0.0031308));
+}
+
+// The canvas is split into tiles of the texture size stored row by row in the layers, returns the position in the
+// tile and the layer.
+fn tile(size: vec2<u32>, canvas_width: u32, position: vec2<u32>) -> vec3<u32> {
+    let tile = position / size;
+    return vec3<u32>(position % size, tile.y * ((canvas_width + size.x - 1u) / size.x) + tile.x);
+}
+
+// The shaders work on premultiplied colors in linear light, 8-bit canvases store them encoded in sRGB.
+fn decode_canvas(color: vec4<f32>) -> vec4<f32> {
+    if !SRGB_CANVAS || color.a <= 0.0 {
+        return color;
+    }
+    return vec4<f32>(srgb_to_linear(color.rgb / color.a) * color.a, color.a);
+}
+
+fn encode_canvas(color: vec4<f32>) -> vec4<f32> {
+    if !SRGB_CANVAS || color.a <= 0.0 {
+        return color;
+    }
+    return vec4<f32>(linear_to_srgb(color.rgb / color.a) * color.a, color.a);
+}
@@ -10,27 +10,116 @@ struct UniformBufferObject {
     anti_aliasing_scale: f32,
     dash_length: f32,
     gap_length: f32,
+    line_join: u32,
+    line_cap: u32,
+    arrow_start: u32,
+    arrow_end: u32,
+    arrow_size: f32,
+    corner_radius: f32,
+    sides: u32,
+    star_ratio: f32,
+    // 0 is aliased, otherwise the mode from `AntiAliasing`.
+    anti_aliasing: u32,
+    // Only the bounding box of the primitive is dispatched, starting at this pixel.
+    origin: vec2<u32>,
+    canvas_size: vec2<u32>,
+    // Samples per axis of a pixel for supersampling.
+    samples: u32,
 };
 @group(0) @binding(1) var<uniform> u: UniformBufferObject;
-@group(0) @binding(2) var texture: texture_storage_2d<rgba8unorm, read_write>;
+@group(0) @binding(2) var texture: texture_storage_2d_array<CANVAS_FORMAT, read_write>;
+@group(0) @binding(3) var text_mask: texture_2d_array<f32>;
 
-@compute @workgroup_size(8, 8, 1)
-fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
-    if any(id.xy >= textureDimensions(texture)) || (s.length < 2 && u.action != 0) {
-        return;
+// Same as the SVG default.
+const MITER_LIMIT = 4.0;
+
+// https://iquilezles.org/articles/distfunctions2d/
+fn sd_triangle(p: vec2<f32>, p0: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>) -> f32 {
+    let e0 = p1 - p0;
+    let e1 = p2 - p1;
+    let e2 = p0 - p2;
+    let v0 = p - p0;
+    let v1 = p - p1;
+    let v2 = p - p2;
+    let pq0 = v0 - e0 * clamp(dot(v0, e0) / dot(e0, e0), 0.0, 1.0);
+    let pq1 = v1 - e1 * clamp(dot(v1, e1) / dot(e1, e1), 0.0, 1.0);
+    let pq2 = v2 - e2 * clamp(dot(v2, e2) / dot(e2, e2), 0.0, 1.0);
+    let s = sign(e0.x * e2.y - e0.y * e2.x);
+    let d = min(min(
+        vec2<f32>(dot(pq0, pq0), s * (v0.x * e0.y - v0.y * e0.x)),
+        vec2<f32>(dot(pq1, pq1), s * (v1.x * e1.y - v1.y * e1.x))),
+        vec2<f32>(dot(pq2, pq2), s * (v2.x * e2.y - v2.y * e2.x)),
+    );
+    return -sqrt(d.x) * sign(d.y);
+}
+
+// https://iquilezles.org/articles/distfunctions2d/
+fn sd_segment(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    return distance(pa, ba * clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0));
+}
+
+// Arrowhead at `tip` pointing in the direction `d`, offset by the stroke where it's filled.
+fn arrowhead(p: vec2<f32>, tip: vec2<f32>, d: vec2<f32>, style: u32) -> f32 {
+    let l = u.arrow_size * u.stroke;
+    let n = vec2<f32>(-d.y, d.x) * l * 0.6;
+    let base = tip - d * l;
+    switch style {
+        // Open
+        case 1u: {
+            return min(sd_segment(p, tip, base + n), sd_segment(p, tip, base - n));
+        }
+        // Triangle
+        case 2u: {
+            return max(sd_triangle(p, tip, base + n, base - n) + u.stroke, 0.0);
+        }
+        // Circle
+        case 3u: {
+            return max(distance(p, tip) - l * 0.5 + u.stroke, 0.0);
+        }
+        // Bar
+        case 4u: {
+            return sd_segment(p, tip + n, tip - n);
+        }
+        default: {
+            return 1e6;
+        }
     }
+}
 
+// Join of the segments `a`-`b` and `b`-`c` offset by the stroke, so that it can be combined with the other SDFs.
+fn line_join(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>, c: vec2<f32>) -> f32 {
+    let t0 = normalize(b - a);
+    let t1 = normalize(c - b);
+    let turn = t0.x * t1.y - t0.y * t1.x;
+    if u.line_join == 1u {
+        return distance(p, b);
+    }
+    if abs(turn) < 1e-4 {
+        return 1e6;
+    }
+    // Offsets of both segments on the outer side of the turn.
+    let n0 = -sign(turn) * vec2<f32>(-t0.y, t0.x) * u.stroke;
+    let n1 = -sign(turn) * vec2<f32>(-t1.y, t1.x) * u.stroke;
+    var d = sd_triangle(p, b, b + n0, b + n1);
+    if u.line_join == 0u {
+        let miter = (n0 + n1) / (1.0 + dot(t0, t1));
+        if length(miter) <= MITER_LIMIT * u.stroke {
+            d = min(d, min(sd_triangle(p, b, b + n0, b + miter), sd_triangle(p, b, b + miter, b + n1)));
+        }
+    }
+    return max(d + u.stroke, 0.0);
+}
+
+// Signed distance of `current_pixel` to the primitive, the stroke is drawn where its absolute value is below the
+// stroke.
+fn shape_sdf(current_pixel: vec2<f32>) -> f32 {
     let p1 = s.points[0];
     let p2 = s.points[1];
-    let current_pixel = vec2<f32>(id.xy);
 
     var sdf = 1e6;
     switch u.action {
-        // Init
-        case 0u: {
-            textureStore(texture, id.xy, u.color);
-            return;
-        }
         // Draw line
         case 1u: {
             // https://iquilezles.org/articles/distfunctions2d/
@@ -52,10 +141,11 @@ fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
         case 2u: {
             // https://iquilezles.org/articles/distfunctions2d/
             // https://www.youtube.com/watch?v=62-pRVZuS5c
-            let p_min = min(p1, p2);
-            let p_max = max(p1, p2);
-            let a = abs(current_pixel - (p_min + p_max) / 2.0) - (p_max - p_min) / 2.0;
-            sdf = length(max(a, vec2<f32>(0.0))) + min(max(a.x, a.y), 0.0);
+            let rotation = rotation();
+            let half_size = abs(to_local(p2, p1, p2, rotation));
+            let r = min(u.corner_radius, min(half_size.x, half_size.y));
+            let a = abs(to_local(current_pixel, p1, p2, rotation)) - half_size + r;
+            sdf = length(max(a, vec2<f32>(0.0))) + min(max(a.x, a.y), 0.0) - r;
         }
         // Draw circle
         case 3u: {
@@ -63,7 +153,10 @@ fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
             sdf = distance(p1, current_pixel) - distance(p1, p2);
         }
         // Draw ellipse
-        case 4u: {}
+        case 4u: {
+            let rotation = rotation();
+            sdf = sd_ellipse(to_local(current_pixel, p1, p2, rotation), abs(to_local(p2, p1, p2, rotation)));
+        }
         // Draw polygon
         case 5u: {
             // https://iquilezles.org/articles/distfunctions2d/
@@ -80,6 +173,99 @@ fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
             }
             sdf = sqrt(d);
         }
+        // Draw polyline
+        case 10u: {
+            var d = 0.0; // Distance along the polyline
+            var nearest_d = 0.0; // Distance along the polyline of the nearest segment or join
+            for (var i = 0u; i + 1 < s.length; i += 1) {
+                let a = s.points[i];
+                let b = s.points[i + 1];
+                let l = distance(a, b);
+                if l > 1e-3 {
+                    // Oriented box around the segment, square caps extend it by the stroke.
+                    let t = (b - a) / l;
+                    let q = current_pixel - a;
+                    let along = dot(q, t);
+                    let start = select(0.0, u.stroke, i == 0 && u.line_cap == 2u);
+                    let end = select(0.0, u.stroke, i + 2 == s.length && u.line_cap == 2u);
+                    let c = abs(vec2<f32>(along - (l + end - start) / 2.0, t.x * q.y - t.y * q.x))
+                        - vec2<f32>((l + start + end) / 2.0, u.stroke);
+                    let segment = length(max(c, vec2<f32>(0.0))) + min(max(c.x, c.y), 0.0) + u.stroke;
+                    if segment < sdf {
+                        sdf = segment;
+                        nearest_d = d + clamp(along, 0.0, l);
+                    }
+                    d += l;
+                }
+                if i + 2 < s.length && distance(b, s.points[i + 2]) > 1e-3 && l > 1e-3 {
+                    let join = line_join(current_pixel, a, b, s.points[i + 2]);
+                    if join < sdf {
+                        sdf = join;
+                        nearest_d = d;
+                    }
+                }
+            }
+            if u.line_cap == 1u {
+                let start = distance(current_pixel, s.points[0]);
+                if start < sdf {
+                    sdf = start;
+                    nearest_d = 0.0;
+                }
+                let end = distance(current_pixel, s.points[s.length - 1]);
+                if end < sdf {
+                    sdf = end;
+                    nearest_d = d;
+                }
+            }
+            let period = u.dash_length + u.gap_length;
+            if period > 0.0 && nearest_d % period >= u.dash_length {
+                sdf = 1e6;
+            }
+        }
+        // Draw Bézier curve or spline
+        case 11u, 12u: {
+            var d = 0.0; // Distance along the curve
+            var nearest_d = 0.0; // Distance along the curve of the nearest flattened segment
+            var a = s.points[0];
+            let segments = curve_segments(u.action);
+            for (var i = 1u; i <= segments; i += 1) {
+                let b = curve_point(u.action, i);
+                let c = current_pixel - a;
+                let e = b - a;
+                let t = clamp(dot(c, e) / max(dot(e, e), 1e-6), 0.0, 1.0);
+                let segment = distance(c, e * t);
+                if segment < sdf {
+                    sdf = segment;
+                    nearest_d = d + t * length(e);
+                }
+                d += length(e);
+                a = b;
+            }
+            let period = u.dash_length + u.gap_length;
+            if period > 0.0 && nearest_d % period >= u.dash_length {
+                sdf = 1e6;
+            }
+        }
+        // Draw arrow
+        case 13u: {
+            let l = distance(p1, p2);
+            let d = (p2 - p1) / max(l, 1e-6);
+            // Triangles replace the ends of the shaft so that their tips stay sharp.
+            let head = min(u.arrow_size * u.stroke, l);
+            let start = select(0.0, head, u.arrow_start == 2u);
+            let end = max(l - select(0.0, head, u.arrow_end == 2u), start);
+            let t = clamp(dot(current_pixel - p1, d), start, end);
+            sdf = distance(current_pixel, p1 + d * t);
+            let period = u.dash_length + u.gap_length;
+            if period > 0.0 && (t - start) % period >= u.dash_length {
+                sdf = 1e6;
+            }
+            sdf = min(sdf, min(arrowhead(current_pixel, p1, -d, u.arrow_start), arrowhead(current_pixel, p2, d, u.arrow_end)));
+        }
+        // Draw regular shape
+        case 14u: {
+            sdf = sd_star(current_pixel, p1, p2, u.sides, u.star_ratio);
+        }
         // Erase
         case 6u: {
             // https://iquilezles.org/articles/distfunctions2d/
@@ -89,24 +275,83 @@ fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
             let b = p2 - p1;
             sdf = distance(a, b * clamp(dot(a, b) / dot(b, b), 0.0, 1.0));
         }
-        default: {
-            return;
+        default: {}
+    }
+    return sdf;
+}
+
+// Coverage of the pixel centered at `current_pixel` by the stroke.
+fn coverage(current_pixel: vec2<f32>) -> f32 {
+    switch u.anti_aliasing {
+        // Smoothstep, the transition is relative to the stroke.
+        case 1u: {
+            if u.anti_aliasing_scale > 0.0 {
+                return 1.0 - smoothstep(
+                    u.stroke * (1.0 - u.anti_aliasing_scale),
+                    u.stroke * (1.0 + u.anti_aliasing_scale),
+                    abs(shape_sdf(current_pixel)),
+                );
+            }
+        }
+        // Analytic, the transition is one pixel wide regardless of the stroke.
+        case 2u: {
+            return clamp(u.stroke + 0.5 - abs(shape_sdf(current_pixel)), 0.0, 1.0);
+        }
+        // Supersampling with samples evenly spread over the pixel, only the pixels that an edge may cross are sampled.
+        case 3u: {
+            let center_distance = abs(shape_sdf(current_pixel));
+            if abs(center_distance - u.stroke) >= 1.0 {
+                return f32(center_distance <= u.stroke);
+            }
+            let samples = clamp(u.samples, 1u, 4u);
+            var covered = 0u;
+            for (var y = 0u; y < samples; y++) {
+                for (var x = 0u; x < samples; x++) {
+                    let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / f32(samples) - 0.5;
+                    covered += u32(abs(shape_sdf(current_pixel + offset)) <= u.stroke);
+                }
+            }
+            return f32(covered) / f32(samples * samples);
         }
+        default: {}
+    }
+    return f32(abs(shape_sdf(current_pixel)) <= u.stroke);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let position = id.xy + u.origin;
+    if any(position >= u.canvas_size) || (s.length < 2 && u.action != 0) {
+        return;
     }
+    let texel = tile(textureDimensions(texture), u.canvas_size.x, position);
+    // The color is straight sRGB, the canvas holds premultiplied colors in linear light.
+    let color = vec4<f32>(srgb_to_linear(u.color.rgb) * u.color.a, u.color.a);
 
-    sdf = abs(sdf);
-    var coverage = 0.0;
-    if u.anti_aliasing_scale > 0.0 {
-        coverage = 1.0 - smoothstep(
-            u.stroke * (1.0 - u.anti_aliasing_scale),
-            u.stroke * (1.0 + u.anti_aliasing_scale),
-            sdf,
-        );
-    } else {
-        coverage = f32(sdf <= u.stroke);
+    var pixel_coverage = 0.0;
+    switch u.action {
+        // Init
+        case 0u: {
+            textureStore(texture, texel.xy, texel.z, encode_canvas(color));
+            return;
+        }
+        // Draw text
+        case 15u: {
+            pixel_coverage = textureLoad(text_mask, texel.xy, texel.z, 0).r;
+        }
+        // Fill, cut rectangle and pick color don't draw here.
+        case 7u, 8u, 9u: {
+            return;
+        }
+        default: {
+            // Pixel centers are in the middle of the pixels, the same as the points snapped to them.
+            pixel_coverage = coverage(vec2<f32>(position) + 0.5);
+        }
     }
-    let blend_alpha = coverage * u.color.a;
-    if blend_alpha > 0.0 {
-        textureStore(texture, id.xy, mix(textureLoad(texture, id.xy), u.color, blend_alpha));
+    if pixel_coverage > 0.0 {
+        // Source over destination: https://www.w3.org/TR/compositing-1/#porterduffcompositingoperators_srcover
+        let source = color * pixel_coverage;
+        let destination = decode_canvas(textureLoad(texture, texel.xy, texel.z));
+        textureStore(texture, texel.xy, texel.z, encode_canvas(source + destination * (1.0 - source.a)));
     }
 }
@@ -0,0 +1,142 @@
+// Blurs, sharpens or convolves the canvas inside a region. Separable filters run in two passes, the first one writes
+// the horizontal pass into a scratch texture and the second one the vertical pass into the front texture.
+struct ConvolutionUniformBufferObject {
+    region_origin: vec2<u32>,
+    region_size: vec2<u32>,
+    canvas_size: vec2<u32>,
+    convolution: u32,
+    radius: u32,
+    direction: vec2<i32>,
+    sigma: f32,
+    amount: f32,
+    threshold: f32,
+    kernel_size: u32,
+    divisor: f32,
+    bias: f32,
+    last_pass: u32,
+    // Row-major 5×5 kernel packed into vectors, 3×3 kernels use the top-left corner.
+    kernel: array<vec4<f32>, 7>,
+}
+
+@group(0) @binding(0) var<uniform> ubo: ConvolutionUniformBufferObject;
+@group(0) @binding(1) var source: texture_2d_array<f32>;
+@group(0) @binding(2) var destination: texture_storage_2d_array<CANVAS_FORMAT, write>;
+@group(0) @binding(3) var original: texture_2d_array<f32>;
+
+const LUMINANCE: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+fn load(texture: texture_2d_array<f32>, position: vec2<i32>) -> vec4<f32> {
+    let clamped = clamp(position, vec2<i32>(0), vec2<i32>(ubo.canvas_size) - 1);
+    let texel = tile(textureDimensions(texture), ubo.canvas_size.x, vec2<u32>(clamped));
+    return decode_canvas(textureLoad(texture, texel.xy, texel.z, 0));
+}
+
+// Kernels and edge detection work on straight sRGB colors, the canvas holds premultiplied colors in linear light.
+fn load_srgb(position: vec2<i32>) -> vec4<f32> {
+    let color = load(source, position);
+    if color.a <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(linear_to_srgb(color.rgb / color.a), color.a);
+}
+
+fn from_srgb(color: vec3<f32>, alpha: f32) -> vec4<f32> {
+    return vec4<f32>(srgb_to_linear(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0))) * alpha, alpha);
+}
+
+fn kernel_weight(i: u32) -> f32 {
+    return ubo.kernel[i / 4u][i % 4u];
+}
+
+// The colors are premultiplied, so that transparent pixels don't darken the result.
+fn blur(position: vec2<i32>) -> vec4<f32> {
+    let radius = i32(ubo.radius);
+    var sum = vec4<f32>(0.0);
+    var weights = 0.0;
+    for (var i = -radius; i <= radius; i++) {
+        var weight = 1.0;
+        // Gaussian blur, box blur has equal weights.
+        if ubo.convolution != 1u {
+            weight = exp(-f32(i * i) / (2.0 * ubo.sigma * ubo.sigma));
+        }
+        sum += load(source, position + i * ubo.direction) * weight;
+        weights += weight;
+    }
+    return sum / weights;
+}
+
+fn apply_kernel(position: vec2<i32>) -> vec3<f32> {
+    let size = i32(ubo.kernel_size);
+    let center = size / 2;
+    var sum = vec3<f32>(0.0);
+    for (var y = 0; y < size; y++) {
+        for (var x = 0; x < size; x++) {
+            sum += load_srgb(position + vec2<i32>(x, y) - center).rgb * kernel_weight(u32(y * 5 + x));
+        }
+    }
+    return sum / ubo.divisor + ubo.bias;
+}
+
+// https://en.wikipedia.org/wiki/Sobel_operator
+fn sobel(position: vec2<i32>) -> vec3<f32> {
+    var gradient = vec2<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let luminance = dot(load_srgb(position + vec2<i32>(x, y)).rgb, LUMINANCE);
+            let weight = f32(2 - abs(x * y));
+            gradient += vec2<f32>(f32(x), f32(y)) * weight * luminance;
+        }
+    }
+    return vec3<f32>(length(gradient));
+}
+
+fn convolve_color(position: vec2<i32>) -> vec4<f32> {
+    let color = load(source, position);
+    switch ubo.convolution {
+        // Gaussian and box blur
+        case 0u, 1u: {
+            return blur(position);
+        }
+        // Unsharp mask
+        case 2u: {
+            let blurred = blur(position);
+            if !bool(ubo.last_pass) {
+                return blurred;
+            }
+            let sharp = load(original, position);
+            let difference = sharp.rgb - blurred.rgb;
+            if max(abs(difference.r), max(abs(difference.g), abs(difference.b))) < ubo.threshold {
+                return sharp;
+            }
+            return vec4<f32>(sharp.rgb + difference * ubo.amount, sharp.a);
+        }
+        // Sobel edge detection
+        case 3u: {
+            return from_srgb(sobel(position), color.a);
+        }
+        // Emboss and custom kernel
+        case 4u, 5u: {
+            return from_srgb(apply_kernel(position), color.a);
+        }
+        default: {
+            return color;
+        }
+    }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn convolve(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= ubo.canvas_size) {
+        return;
+    }
+    let texel = tile(textureDimensions(destination), ubo.canvas_size.x, id.xy);
+    let local = id.xy - ubo.region_origin;
+    // The subtraction wraps around for pixels before the region origin.
+    if bool(ubo.last_pass) && (local.x >= ubo.region_size.x || local.y >= ubo.region_size.y) {
+        textureStore(destination, texel.xy, texel.z, textureLoad(original, texel.xy, texel.z, 0));
+        return;
+    }
+    let color = clamp(convolve_color(vec2<i32>(id.xy)), vec4<f32>(0.0), vec4<f32>(1.0));
+    // Premultiplied color channels can't exceed alpha.
+    textureStore(destination, texel.xy, texel.z, encode_canvas(vec4<f32>(min(color.rgb, vec3<f32>(color.a)), color.a)));
+}
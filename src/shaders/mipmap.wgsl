@@ -0,0 +1,23 @@
+// Downsamples a mip level of the canvas tiles into the next one, the z coordinate indexes the layers to update.
+// Canvases of several tiles use power of two tiles, so every destination texel covers texels of a single tile.
+@group(0) @binding(0) var source: texture_2d_array<f32>;
+@group(0) @binding(1) var destination: texture_storage_2d_array<CANVAS_FORMAT, write>;
+@group(0) @binding(2) var<storage, read> layers: array<u32>;
+
+@compute @workgroup_size(8, 8, 1)
+fn downsample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let layer = layers[id.z];
+    let source_size = textureDimensions(source);
+    // The colors are premultiplied, so that transparent pixels don't darken the result.
+    var sum = vec4<f32>(0.0);
+    for (var y = 0u; y < 2u; y++) {
+        for (var x = 0u; x < 2u; x++) {
+            sum += decode_canvas(textureLoad(source, min(id.xy * 2u + vec2<u32>(x, y), source_size - 1u), layer, 0));
+        }
+    }
+    textureStore(destination, id.xy, layer, encode_canvas(sum * 0.25));
+}
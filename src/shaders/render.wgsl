@@ -1,6 +1,10 @@
 struct VertexUniformBufferObject {
     scale: vec2<f32>,
     offset: vec2<f32>,
+    transform: mat2x2<f32>, // View rotation and flip
+    window_size: vec2<f32>,
+    canvas_size: vec2<f32>,
+    tile_size: vec2<f32>,
 }
 @group(0) @binding(0) var<uniform> vu: VertexUniformBufferObject;
 
@@ -9,11 +13,19 @@ struct VertexOutput {
     @builtin(position) position: vec4<f32>,
 }
 
+// Every instance is a visible tile, the quad is shrunk to the part of the canvas the tile covers.
 @vertex
-fn vertex(@location(0) position: vec2<f32>) -> VertexOutput {
+fn vertex(@location(0) position: vec2<f32>, @location(1) layer: u32) -> VertexOutput {
+    let columns = u32(ceil(vu.canvas_size.x / vu.tile_size.x));
+    let tile_origin = vec2<f32>(f32(layer % columns), f32(layer / columns)) * vu.tile_size;
+    let tile_extent = min(vu.tile_size, vu.canvas_size - tile_origin);
+    let pixel = tile_origin + fma(position, vec2<f32>(0.5, -0.5), vec2<f32>(0.5)) * tile_extent;
+    let uv = pixel / vu.canvas_size;
+    let quad = fma(uv, vec2<f32>(2.0, -2.0), vec2<f32>(-1.0, 1.0));
     return VertexOutput(
-        fma(position, vec2<f32>(0.5, -0.5), vec2<f32>(0.5)),
-        vec4<f32>((position + vu.offset) * vu.scale, 0.0, 1.0),
+        uv,
+        // The view is rotated in window pixels, where both axes have the same scale.
+        vec4<f32>(vu.transform * ((quad + vu.offset) * vu.scale * vu.window_size) / vu.window_size, 0.0, 1.0),
     );
 }
 
@@ -26,44 +38,145 @@ struct FragmentUniformBufferObject {
     grid_scale: vec2<f32>,
     action: u32,
     preview: u32, // bool
+    corner_radius: f32,
+    sides: u32,
+    star_ratio: f32,
+    grid_subdivisions: u32,
+    grid_color: vec4<f32>,
+    pixel_grid: u32, // bool
+    linear_filtering: u32, // bool
 }
 @group(0) @binding(2) var<uniform> fu: FragmentUniformBufferObject;
-@group(0) @binding(3) var texture: texture_2d<f32>;
-@group(0) @binding(4) var texture_sampler: sampler;
+@group(0) @binding(3) var texture: texture_2d_array<f32>;
+
+// Loads the texel of the canvas at the mip `level` from the tile that holds it. The tiles are a power of two in size
+// when there are more of them, so their mip levels line up.
+fn load_canvas(texel: vec2<i32>, level: u32) -> vec4<f32> {
+    let tile_size = max(vec2<u32>(vu.tile_size) >> vec2<u32>(level), vec2<u32>(1u));
+    let size = max(vec2<u32>(vu.canvas_size) >> vec2<u32>(level), vec2<u32>(1u));
+    let position = vec2<u32>(clamp(texel, vec2<i32>(0), vec2<i32>(size) - 1));
+    let tile = position / tile_size;
+    let columns = u32(ceil(vu.canvas_size.x / vu.tile_size.x));
+    return decode_canvas(textureLoad(texture, position % tile_size, tile.y * columns + tile.x, i32(level)));
+}
+
+fn sample_canvas_level(frag_coord: vec2<f32>, level: u32) -> vec4<f32> {
+    let position = frag_coord / exp2(f32(level)) - 0.5;
+    let texel = vec2<i32>(floor(position));
+    let t = fract(position);
+    let top = mix(load_canvas(texel, level), load_canvas(texel + vec2<i32>(1, 0), level), t.x);
+    let bottom = mix(load_canvas(texel + vec2<i32>(0, 1), level), load_canvas(texel + vec2<i32>(1, 1), level), t.x);
+    return mix(top, bottom, t.y);
+}
+
+// Trilinear filtering done by hand, a sampler would clamp at the tile edges and leave seams between the tiles.
+fn sample_canvas(frag_coord: vec2<f32>, footprint: f32) -> vec4<f32> {
+    let last_level = textureNumLevels(texture) - 1u;
+    let lod = clamp(log2(footprint), 0.0, f32(last_level));
+    let level = u32(lod);
+    let finer = sample_canvas_level(frag_coord, level);
+    if level == last_level {
+        return finer;
+    }
+    return mix(finer, sample_canvas_level(frag_coord, level + 1u), fract(lod));
+}
+
+// Overlays the pixel grid and the user-defined grid, `pixel_size` is the size of a screen pixel in canvas pixels.
+fn grid(color: vec4<f32>, frag_coord: vec2<f32>, pixel_size: vec2<f32>) -> vec4<f32> {
+    var rgb = color.rgb;
+    if bool(fu.pixel_grid) {
+        // Fade in when the canvas pixels are large enough.
+        let fade = smoothstep(4.0, 8.0, 1.0 / max(pixel_size.x, pixel_size.y));
+        let d = abs(fract(frag_coord + 0.5) - 0.5) / pixel_size;
+        let line = 1.0 - smoothstep(0.5, 1.0, min(d.x, d.y));
+        rgb = mix(rgb, select(vec3<f32>(0.0), vec3<f32>(1.0), dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722)) < 0.179), line * fade * 0.5);
+    }
+    if all(fu.grid_scale > vec2<f32>(0.0)) {
+        let subdivision = fu.grid_scale / f32(max(fu.grid_subdivisions, 1u));
+        let d0 = abs(fract(frag_coord / subdivision + 0.5) - 0.5) * subdivision / pixel_size;
+        let d1 = abs(fract(frag_coord / fu.grid_scale + 0.5) - 0.5) * fu.grid_scale / pixel_size;
+        let line = max(
+            (1.0 - smoothstep(0.5, 1.0, min(d0.x, d0.y))) * 0.5,
+            1.0 - smoothstep(0.5, 1.0, min(d1.x, d1.y)),
+        );
+        rgb = mix(rgb, fu.grid_color.rgb, line * fu.grid_color.a);
+    }
+    return vec4<f32>(rgb, color.a);
+}
 
 @fragment
 fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
-    let color = textureSample(texture, texture_sampler, uv);
+    let frag_coord = uv * vu.canvas_size;
+    // Canvas pixels per window pixel.
+    let footprint = max(length(dpdx(frag_coord)), length(dpdy(frag_coord)));
+    // The canvas holds premultiplied colors in linear light, the surface expects straight sRGB ones.
+    var canvas: vec4<f32>;
+    if fu.linear_filtering != 0u {
+        canvas = sample_canvas(frag_coord, footprint);
+    } else {
+        canvas = load_canvas(vec2<i32>(floor(frag_coord)), 0u);
+    }
+    let straight = select(vec4<f32>(0.0), vec4<f32>(linear_to_srgb(canvas.rgb / canvas.a), canvas.a), canvas.a > 0.0);
+    let color = grid(straight, frag_coord, fwidth(frag_coord));
 
     if s.length < 2 {
         return color;
     }
 
-    // TODO: Pass this as an uniform?
-    let frag_coord = uv * vec2<f32>(textureDimensions(texture));
-
     let p1 = s.points[0];
     let p2 = s.points[1];
 
     var sdf = 1e6;
     switch fu.action {
-        case 1u, 2u, 3u, 4u, 8u: {
+        case 1u, 3u, 13u, 14u: {
             sdf = min(distance(p1, frag_coord), distance(p2, frag_coord)) - 5.0;
         }
-        case 5u: {
+        case 2u, 4u, 8u: {
+            sdf = min(distance(p1, frag_coord), distance(p2, frag_coord)) - 5.0;
+            // Rotation handle connected to the middle of the top edge
+            if s.length > 2 {
+                let rotation = rotation();
+                let top = (p1 + p2) / 2.0 + vec2<f32>(rotation.y, -rotation.x) * abs(to_local(p2, p1, p2, rotation)).y;
+                let a = frag_coord - top;
+                let b = s.points[2] - top;
+                sdf = min(min(sdf, distance(s.points[2], frag_coord) - 5.0), distance(a, b * clamp(dot(a, b) / max(dot(b, b), 1e-6), 0.0, 1.0)));
+            }
+        }
+        case 5u, 10u, 12u: {
             for (var i = 0u; i < s.length; i += 1) {
                 sdf = min(sdf, distance(s.points[i], frag_coord));
             }
             sdf -= 5.0;
         }
+        // Draw Bézier curve
+        case 11u: {
+            for (var i = 0u; i < s.length; i += 1) {
+                sdf = min(sdf, distance(s.points[i], frag_coord) - 5.0);
+            }
+            // Control handles
+            if s.length > 2 {
+                for (var i = 0u; i < 2; i += 1) {
+                    let a = frag_coord - s.points[i * (s.length - 1)];
+                    let b = s.points[i * (s.length - 3) + 1] - s.points[i * (s.length - 1)];
+                    sdf = min(sdf, distance(a, b * clamp(dot(a, b) / dot(b, b), 0.0, 1.0)));
+                }
+            }
+        }
+        // Text box
+        case 15u: {
+            let p_min = min(p1, p2) - 2.0;
+            let p_max = max(p1, p2) + 2.0;
+            let a = abs(frag_coord - (p_min + p_max) / 2.0) - (p_max - p_min) / 2.0;
+            sdf = length(max(a, vec2<f32>(0.0))) + min(max(a.x, a.y), 0.0);
+        }
         default: {
             return color;
         }
     }
     if !bool(fu.preview) {
         switch fu.action {
-            // Draw line
-            case 1u: {
+            // Draw line or arrow
+            case 1u, 13u: {
                 // https://iquilezles.org/articles/distfunctions2d/
                 // https://www.youtube.com/watch?v=PMltMdi1Wzg
                 // https://www.desmos.com/calculator/afsee2587r
@@ -79,10 +192,12 @@ fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 // sdf = fwidthFine(min(sdf, max(a.x, a.y) - 1.0));
                 // https://iquilezles.org/articles/distfunctions2d/
                 // https://www.youtube.com/watch?v=62-pRVZuS5c
-                let p_min = min(p1, p2);
-                let p_max = max(p1, p2);
-                let a = abs(frag_coord - (p_min + p_max) / 2.0) - (p_min - p_max) / 2.0;
-                sdf = min(sdf, length(max(a, vec2<f32>(0.0))) + min(max(a.x, a.y), 0.0));
+                let rotation = rotation();
+                let half_size = abs(to_local(p2, p1, p2, rotation));
+                let r = select(0.0, min(fu.corner_radius, min(half_size.x, half_size.y)), fu.action == 2u);
+                let a = abs(to_local(frag_coord, p1, p2, rotation)) - half_size + r;
+                let b = length(max(a, vec2<f32>(0.0))) + min(max(a.x, a.y), 0.0) - r;
+                sdf = max(min(sdf, b), -max(sdf, b));
             }
             // Draw circle
             case 3u: {
@@ -91,7 +206,11 @@ fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 sdf = max(min(sdf, a), -max(sdf, a)); // Simple OR doesn't work for circles, XOR is used: https://iquilezles.org/articles/sdfxor/
             }
             // Draw ellipse
-            case 4u: {}
+            case 4u: {
+                let rotation = rotation();
+                let a = sd_ellipse(to_local(frag_coord, p1, p2, rotation), abs(to_local(p2, p1, p2, rotation)));
+                sdf = max(min(sdf, a), -max(sdf, a));
+            }
             // Draw polygon
             case 5u: {
                 // https://iquilezles.org/articles/distfunctions2d/
@@ -108,6 +227,33 @@ fn fragment(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
                 }
                 sdf = min(sdf, sqrt(d));
             }
+            // Draw regular shape
+            case 14u: {
+                let a = sd_star(frag_coord, p1, p2, fu.sides, fu.star_ratio);
+                sdf = max(min(sdf, a), -max(sdf, a));
+            }
+            // Draw polyline
+            case 10u: {
+                for (var i = 0u; i + 1 < s.length; i += 1) {
+                    let a = frag_coord - s.points[i];
+                    let b = s.points[i + 1] - s.points[i];
+                    sdf = min(sdf, distance(a, b * clamp(dot(a, b) / dot(b, b), 0.0, 1.0)));
+                }
+            }
+            // Draw Bézier curve or spline
+            case 11u, 12u: {
+                var a = s.points[0];
+                let segments = curve_segments(fu.action);
+                for (var i = 1u; i <= segments; i += 1) {
+                    let b = curve_point(fu.action, i);
+                    let c = frag_coord - a;
+                    let e = b - a;
+                    sdf = min(sdf, distance(c, e * clamp(dot(c, e) / max(dot(e, e), 1e-6), 0.0, 1.0)));
+                    a = b;
+                }
+            }
+            // Text box, the text itself is only composited by the compute shader.
+            case 15u: {}
             default: {
                 return color;
             }
@@ -0,0 +1,92 @@
+// Shape helpers shared by the compute shader and the fragment shader, which both declare the points as `s`.
+
+const PI = 3.14159265358979323846264338327950288;
+
+// Regular polygon or star centered at `center` with a vertex at `corner`, `ratio` is the inner radius relative to the
+// outer one, the inner vertices of regular polygons lie on the middle of their edges.
+fn sd_star(p: vec2<f32>, center: vec2<f32>, corner: vec2<f32>, sides: u32, ratio: f32) -> f32 {
+    let r = distance(center, corner);
+    let n = f32(max(sides, 3u));
+    let sector = 2.0 * PI / n;
+    let q = p - center;
+    let v = corner - center;
+    // Angle relative to the vertex, mirrored into the first half of its sector.
+    let a = atan2(v.x * q.y - v.y * q.x, dot(v, q));
+    let phi = abs(a - sector * round(a / sector));
+    let local = length(q) * vec2<f32>(cos(phi), sin(phi));
+    let outer = vec2<f32>(r, 0.0);
+    let inner = r * select(cos(PI / n), ratio, ratio > 0.0) * vec2<f32>(cos(PI / n), sin(PI / n));
+    let e = inner - outer;
+    let w = local - outer;
+    let d = distance(w, e * clamp(dot(w, e) / dot(e, e), 0.0, 1.0));
+    return select(d, -d, e.x * w.y - e.y * w.x > 0.0);
+}
+
+// Number of line segments a Bézier curve or a span of a spline is flattened into.
+const CURVE_SEGMENTS = 32u;
+
+fn curve_segments(action: u32) -> u32 {
+    if action == 12u {
+        return (s.length - 1) * CURVE_SEGMENTS;
+    }
+    return 2 * CURVE_SEGMENTS;
+}
+
+// Point on the Bézier curve (its degree is given by the number of points) or on the Catmull-Rom spline after `i`
+// flattened segments.
+fn curve_point(action: u32, i: u32) -> vec2<f32> {
+    if action == 12u {
+        // https://en.wikipedia.org/wiki/Cubic_Hermite_spline#Catmull%E2%80%93Rom_spline
+        let span = min(i / CURVE_SEGMENTS, s.length - 2);
+        let t = f32(i - span * CURVE_SEGMENTS) / f32(CURVE_SEGMENTS);
+        let p0 = s.points[max(span, 1u) - 1];
+        let p1 = s.points[span];
+        let p2 = s.points[span + 1];
+        let p3 = s.points[min(span + 2, s.length - 1)];
+        return 0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+            + (3.0 * (p1 - p2) + p3 - p0) * t * t * t);
+    }
+    // https://en.wikipedia.org/wiki/B%C3%A9zier_curve#Specific_cases
+    let t = f32(i) / f32(curve_segments(action));
+    let v = 1.0 - t;
+    switch s.length {
+        case 3u: {
+            return v * v * s.points[0] + 2.0 * v * t * s.points[1] + t * t * s.points[2];
+        }
+        case 4u: {
+            return v * v * v * s.points[0] + 3.0 * v * v * t * s.points[1] + 3.0 * v * t * t * s.points[2]
+                + t * t * t * s.points[3];
+        }
+        default: {
+            return mix(s.points[0], s.points[1], t);
+        }
+    }
+}
+
+// Rotation of a rectangle, an ellipse or a selection as (cos, sin), given by its rotation handle (the third point) which
+// is above the center when not rotated.
+fn rotation() -> vec2<f32> {
+    if s.length < 3 {
+        return vec2<f32>(1.0, 0.0);
+    }
+    let d = s.points[2] - (s.points[0] + s.points[1]) / 2.0;
+    if length(d) < 1e-3 {
+        return vec2<f32>(1.0, 0.0);
+    }
+    let n = normalize(d);
+    return vec2<f32>(-n.y, n.x);
+}
+
+// `p` relative to the center of the shape given by its opposite corners `p1` and `p2` and rotated by `-r`.
+fn to_local(p: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>, r: vec2<f32>) -> vec2<f32> {
+    let q = p - (p1 + p2) / 2.0;
+    return vec2<f32>(r.x * q.x + r.y * q.y, r.x * q.y - r.y * q.x);
+}
+
+// https://iquilezles.org/articles/ellipsedist/
+fn sd_ellipse(p: vec2<f32>, radii: vec2<f32>) -> f32 {
+    let r = max(radii, vec2<f32>(1e-3));
+    let k0 = length(p / r);
+    let k1 = length(p / (r * r));
+    return k0 * (k0 - 1.0) / max(k1, 1e-6);
+}
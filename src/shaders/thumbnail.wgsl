@@ -0,0 +1,38 @@
+// Downsamples the tiled canvas into the navigator thumbnail.
+struct ThumbnailUniformBufferObject {
+    canvas_size: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> ubo: ThumbnailUniformBufferObject;
+@group(0) @binding(1) var source: texture_2d_array<f32>;
+@group(0) @binding(2) var destination: texture_storage_2d_array<rgba8unorm, write>;
+
+// Samples per thumbnail pixel along each axis.
+const SAMPLES = 4u;
+
+@compute @workgroup_size(8, 8, 1)
+fn thumbnail(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let scale = vec2<f32>(ubo.canvas_size) / vec2<f32>(size);
+    let source_size = textureDimensions(source);
+    // The colors are premultiplied, so that transparent pixels don't darken the result.
+    var sum = vec4<f32>(0.0);
+    for (var y = 0u; y < SAMPLES; y++) {
+        for (var x = 0u; x < SAMPLES; x++) {
+            let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / f32(SAMPLES);
+            let position = min(vec2<u32>((vec2<f32>(id.xy) + offset) * scale), ubo.canvas_size - 1u);
+            let texel = tile(source_size, ubo.canvas_size.x, position);
+            sum += decode_canvas(textureLoad(source, texel.xy, texel.z, 0));
+        }
+    }
+    // egui expects sRGB colors premultiplied after the encoding.
+    let alpha = sum.a / f32(SAMPLES * SAMPLES);
+    if alpha > 0.0 {
+        textureStore(destination, id.xy, 0, vec4<f32>(linear_to_srgb(sum.rgb / sum.a) * alpha, alpha));
+    } else {
+        textureStore(destination, id.xy, 0, vec4<f32>(0.0));
+    }
+}
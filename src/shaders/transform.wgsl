@@ -0,0 +1,24 @@
+// Copies the canvas into a flipped, rotated or cropped one.
+struct TransformUniformBufferObject {
+    // The source pixel of the destination pixel (x, y) is origin + x * x_axis + y * y_axis.
+    origin: vec2<i32>,
+    x_axis: vec2<i32>,
+    y_axis: vec2<i32>,
+    source_size: vec2<u32>,
+    canvas_size: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> ubo: TransformUniformBufferObject;
+@group(0) @binding(1) var source: texture_2d_array<f32>;
+@group(0) @binding(2) var destination: texture_storage_2d_array<CANVAS_FORMAT, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn transform(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= ubo.canvas_size) {
+        return;
+    }
+    let position = vec2<u32>(ubo.origin + i32(id.x) * ubo.x_axis + i32(id.y) * ubo.y_axis);
+    let source_texel = tile(textureDimensions(source), ubo.source_size.x, position);
+    let texel = tile(textureDimensions(destination), ubo.canvas_size.x, id.xy);
+    textureStore(destination, texel.xy, texel.z, textureLoad(source, source_texel.xy, source_texel.z, 0));
+}
@@ -0,0 +1,179 @@
+use std::{borrow::Cow, fs, io, path::PathBuf};
+
+use ab_glyph::{Font, FontVec, InvalidFont, PxScale, ScaleFont, point};
+use thiserror::Error;
+
+use crate::helpers::{Size, TextAlignment};
+
+// Slant of the synthetic italic.
+const ITALIC_SLANT: f32 = 0.2;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Equivalent to [`std::io::Error`]
+    #[error("Unable to read the font file: {0}")]
+    Io(#[from] io::Error),
+    /// Equivalent to [`ab_glyph::InvalidFont`]
+    #[error("Invalid font data.")]
+    InvalidFont(#[from] InvalidFont),
+}
+
+enum FontSource {
+    Bundled(Cow<'static, [u8]>),
+    File(PathBuf),
+}
+
+pub struct FontFamily {
+    pub name: String,
+    source: FontSource,
+}
+
+impl FontFamily {
+    /// Returns the fonts bundled with egui followed by the fonts found in the usual system font directories.
+    pub fn all() -> Vec<Self> {
+        let mut font_families: Vec<_> = egui::FontDefinitions::default()
+            .font_data
+            .into_iter()
+            .filter(|(name, _)| !name.contains("emoji") && !name.contains("Emoji"))
+            .map(|(name, font_data)| Self {
+                name,
+                source: FontSource::Bundled(font_data.font.clone()),
+            })
+            .collect();
+        let mut directories = vec![
+            PathBuf::from("/usr/share/fonts"),
+            PathBuf::from("/usr/local/share/fonts"),
+            PathBuf::from("/Library/Fonts"),
+            PathBuf::from("/System/Library/Fonts"),
+            PathBuf::from("C:\\Windows\\Fonts"),
+        ];
+        if let Some(font_dir) = dirs::font_dir() {
+            directories.push(font_dir);
+        }
+        let mut system_font_families = vec![];
+        while let Some(directory) = directories.pop() {
+            let Ok(entries) = fs::read_dir(&directory) else {
+                continue;
+            };
+            for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+                if path.is_dir() {
+                    directories.push(path);
+                } else if path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| {
+                    ["ttf", "otf", "ttc"].iter().any(|supported| extension.eq_ignore_ascii_case(supported))
+                }) && let Some(name) = path.file_stem().and_then(|name| name.to_str())
+                {
+                    system_font_families.push(Self {
+                        name: name.to_owned(),
+                        source: FontSource::File(path.clone()),
+                    });
+                }
+            }
+        }
+        system_font_families.sort_by(|a, b| a.name.cmp(&b.name));
+        font_families.append(&mut system_font_families);
+        font_families
+    }
+
+    pub fn load(&self) -> Result<FontVec, Error> {
+        let data = match &self.source {
+            FontSource::Bundled(data) => data.to_vec(),
+            FontSource::File(path) => fs::read(path)?,
+        };
+        Ok(FontVec::try_from_vec_and_index(data, 0)?)
+    }
+}
+
+#[derive(Default)]
+pub struct TextMask {
+    pub size: Size<u32>,
+    pub coverage: Vec<u8>,
+}
+
+/// Rasterizes the lines of `text` into an anti-aliased coverage mask, bold and italic are synthesized.
+pub fn rasterize(
+    font: &FontVec,
+    text: &str,
+    font_size: f32,
+    bold: bool,
+    italic: bool,
+    alignment: TextAlignment,
+) -> TextMask {
+    let font = font.as_scaled(PxScale::from(font_size));
+    let line_height = font.height() + font.line_gap();
+    let embolden = if bold { (font_size / 24.0).ceil() } else { 0.0 };
+    let slant = if italic { ITALIC_SLANT } else { 0.0 };
+    let line_widths: Vec<_> = text
+        .split('\n')
+        .map(|line| {
+            let mut previous = None;
+            line.chars().fold(0.0, |width, c| {
+                let id = font.glyph_id(c);
+                let kern = previous.map_or(0.0, |previous| font.kern(previous, id));
+                previous = Some(id);
+                width + kern + font.h_advance(id)
+            })
+        })
+        .collect();
+    let width = font.ascent().mul_add(slant, line_widths.iter().copied().fold(0.0, f32::max) + embolden);
+    #[allow(clippy::cast_precision_loss)]
+    let height = line_height * line_widths.len() as f32;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let size = Size::new(width.ceil() as u32, height.ceil() as u32);
+    let mut coverage = vec![0u8; (size.width * size.height) as usize];
+    for (line_idx, (line, line_width)) in text.split('\n').zip(&line_widths).enumerate() {
+        #[allow(clippy::cast_precision_loss)]
+        let baseline = line_height.mul_add(line_idx as f32, font.ascent());
+        let mut x = match alignment {
+            TextAlignment::Left => 0.0,
+            TextAlignment::Center => (width - line_width) / 2.0,
+            TextAlignment::Right => width - line_width,
+        };
+        let mut previous = None;
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += font.kern(previous, id);
+            }
+            previous = Some(id);
+            if let Some(outlined) = font.outline_glyph(id.with_scale_and_position(font.scale(), point(x, baseline))) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|glyph_x, glyph_y, glyph_coverage| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let (pixel_x, pixel_y) = (bounds.min.x + glyph_x as f32, bounds.min.y + glyph_y as f32);
+                    // Shear the glyph around the baseline, the coverage is split between the two nearest pixels.
+                    let sheared_x = (baseline - pixel_y).mul_add(slant, pixel_x);
+                    let fraction = sheared_x - sheared_x.floor();
+                    for (offset, weight) in [(0.0, 1.0 - fraction), (1.0, fraction)] {
+                        #[allow(clippy::cast_possible_truncation)]
+                        let (x, y) = ((sheared_x.floor() + offset) as i64, pixel_y as i64);
+                        if x < 0 || y < 0 || x >= i64::from(size.width) || y >= i64::from(size.height) {
+                            continue;
+                        }
+                        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                        {
+                            let idx = y as usize * size.width as usize + x as usize;
+                            let value = (glyph_coverage * weight * 255.0).round() as u8;
+                            coverage[idx] = coverage[idx].saturating_add(value);
+                        }
+                    }
+                });
+            }
+            x += font.h_advance(id);
+        }
+    }
+    // Dilate the glyphs horizontally.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let embolden = embolden as usize;
+    if embolden > 0 {
+        for row in coverage.chunks_mut(size.width as usize) {
+            for x in (0..row.len()).rev() {
+                row[x] = row[x.saturating_sub(embolden)..=x].iter().copied().max().unwrap_or_default();
+            }
+        }
+    }
+    TextMask {
+        size,
+        coverage,
+    }
+}
@@ -0,0 +1,207 @@
+use wgpu::{Extent3d, Origin3d};
+
+use crate::helpers::Size;
+
+/// Preferred side of a tile in pixels, smaller canvases use a single tile of their own size.
+pub const TILE_SIZE: u32 = 2048;
+
+/// Splits the canvas into tiles stored row by row in the layers of array textures, so that the canvas isn't bounded
+/// by the maximum texture size.
+#[derive(Clone, Copy)]
+pub struct Tiling {
+    pub canvas_size: Size<u32>,
+    pub tile_size: Size<u32>,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Tiling {
+    pub const fn new(canvas_size: Size<u32>, max_tile_size: u32) -> Self {
+        let max_tile_size = if max_tile_size < TILE_SIZE { max_tile_size } else { TILE_SIZE };
+        let tile_size = Size::new(
+            if canvas_size.width < max_tile_size { canvas_size.width } else { max_tile_size },
+            if canvas_size.height < max_tile_size { canvas_size.height } else { max_tile_size },
+        );
+        Self {
+            canvas_size,
+            tile_size,
+            columns: canvas_size.width.div_ceil(tile_size.width),
+            rows: canvas_size.height.div_ceil(tile_size.height),
+        }
+    }
+
+    pub const fn layers(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    /// Extent of the array textures holding the tiles.
+    pub const fn extent(&self) -> Extent3d {
+        Extent3d {
+            width: self.tile_size.width,
+            height: self.tile_size.height,
+            depth_or_array_layers: self.layers(),
+        }
+    }
+
+    /// Returns the part of the canvas covered by the tile in `layer`, the last column and row are cut off by the
+    /// canvas edges.
+    pub fn tile(&self, layer: u32) -> (Origin3d, Extent3d) {
+        let x = layer % self.columns * self.tile_size.width;
+        let y = layer / self.columns * self.tile_size.height;
+        (
+            Origin3d {
+                x,
+                y,
+                z: 0,
+            },
+            Extent3d {
+                width: self.tile_size.width.min(self.canvas_size.width - x),
+                height: self.tile_size.height.min(self.canvas_size.height - y),
+                depth_or_array_layers: 1,
+            },
+        )
+    }
+
+    /// Splits a region of the canvas along the tile edges. Yields the origin in the array textures (`z` is the layer),
+    /// the origin in the canvas and the extent of each part.
+    pub fn split(&self, origin: Origin3d, extent: Extent3d) -> impl Iterator<Item = (Origin3d, Origin3d, Extent3d)> {
+        let Size {
+            width: tile_width,
+            height: tile_height,
+        } = self.tile_size;
+        let (x_max, y_max) = (origin.x + extent.width, origin.y + extent.height);
+        let columns = origin.x / tile_width..x_max.div_ceil(tile_width);
+        let rows = origin.y / tile_height..y_max.div_ceil(tile_height);
+        rows.flat_map(move |row| columns.clone().map(move |column| (column, row))).map(move |(column, row)| {
+            let x = origin.x.max(column * tile_width);
+            let y = origin.y.max(row * tile_height);
+            (
+                Origin3d {
+                    x: x - column * tile_width,
+                    y: y - row * tile_height,
+                    z: row * self.columns + column,
+                },
+                Origin3d {
+                    x,
+                    y,
+                    z: 0,
+                },
+                Extent3d {
+                    width: x_max.min((column + 1) * tile_width) - x,
+                    height: y_max.min((row + 1) * tile_height) - y,
+                    depth_or_array_layers: 1,
+                },
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> (Origin3d, Extent3d) {
+        (
+            Origin3d {
+                x,
+                y,
+                z: 0,
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        )
+    }
+
+    #[test]
+    fn small_canvas_is_one_tile() {
+        let tiling = Tiling::new(Size::new(300, 200), 8192);
+        assert_eq!((tiling.tile_size.width, tiling.tile_size.height), (300, 200));
+        assert_eq!((tiling.columns, tiling.rows), (1, 1));
+        assert_eq!(tiling.tile(0), region(0, 0, 300, 200));
+        let (origin, extent) = region(10, 20, 30, 40);
+        let parts: Vec<_> = tiling.split(origin, extent).collect();
+        assert_eq!(parts, [(origin, origin, extent)]);
+    }
+
+    #[test]
+    fn tile_size_is_limited_by_the_device() {
+        let tiling = Tiling::new(Size::new(3000, 1000), 1024);
+        assert_eq!((tiling.tile_size.width, tiling.tile_size.height), (1024, 1000));
+        assert_eq!((tiling.columns, tiling.rows), (3, 1));
+    }
+
+    #[test]
+    fn last_tiles_are_cut_off() {
+        let tiling = Tiling::new(Size::new(5000, 3000), 8192);
+        assert_eq!((tiling.columns, tiling.rows), (3, 2));
+        assert_eq!(tiling.layers(), 6);
+        assert_eq!(tiling.tile(2), region(4096, 0, 904, 2048));
+        assert_eq!(tiling.tile(5), region(4096, 2048, 904, 952));
+        let (origin, extent) = region(4500, 2500, 500, 500);
+        let parts: Vec<_> = tiling.split(origin, extent).collect();
+        assert_eq!(parts, [(
+            Origin3d {
+                x: 404,
+                y: 452,
+                z: 5,
+            },
+            origin,
+            extent
+        )]);
+    }
+
+    #[test]
+    fn split_crosses_tile_edges() {
+        let tiling = Tiling::new(Size::new(5000, 3000), 8192);
+        let (origin, extent) = region(2000, 2000, 100, 100);
+        let parts: Vec<_> = tiling.split(origin, extent).collect();
+        assert_eq!(parts.len(), 4);
+        // Row by row, the parts cover the region exactly.
+        let layers: Vec<_> = parts.iter().map(|(tile_origin, _, _)| tile_origin.z).collect();
+        assert_eq!(layers, [0, 1, 3, 4]);
+        let (canvas_origin, part_extent) = region(2000, 2000, 48, 48);
+        assert_eq!(
+            parts[0],
+            (
+                Origin3d {
+                    z: 0,
+                    ..canvas_origin
+                },
+                canvas_origin,
+                part_extent
+            )
+        );
+        let (canvas_origin, part_extent) = region(2048, 2048, 52, 52);
+        assert_eq!(
+            parts[3],
+            (
+                Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: 4
+                },
+                canvas_origin,
+                part_extent
+            )
+        );
+        let area: u32 = parts.iter().map(|(_, _, extent)| extent.width * extent.height).sum();
+        assert_eq!(area, 100 * 100);
+    }
+
+    #[test]
+    fn whole_canvas_splits_into_tiles() {
+        let tiling = Tiling::new(Size::new(5000, 3000), 8192);
+        let (origin, extent) = region(0, 0, 5000, 3000);
+        for (layer, (tile_origin, canvas_origin, tile_extent)) in (0..).zip(tiling.split(origin, extent)) {
+            assert_eq!(tile_origin, Origin3d {
+                x: 0,
+                y: 0,
+                z: layer
+            });
+            assert_eq!((canvas_origin, tile_extent), tiling.tile(layer));
+        }
+    }
+}